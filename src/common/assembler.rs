@@ -0,0 +1,342 @@
+use super::isa::{Instruction, Isa, Operand, OperandKind};
+use std::{collections::HashMap, error::Error, fmt};
+
+/// Something wrong with a program's source, at a 1-based line and column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// Every error found while assembling a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssemblyErrors(pub Vec<SourceError>);
+
+impl Error for AssemblyErrors {}
+
+impl fmt::Display for AssemblyErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, e) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", e)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where an instruction came from in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingEntry {
+    pub address: usize,
+    pub line: usize,
+    pub text: String,
+    /// Every label pointing at the instruction, whether on its line or an earlier one
+    pub labels: Vec<String>,
+}
+
+impl fmt::Display for ListingEntry {
+    /// The line number and source, along with any labels from earlier lines
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        for label in &self.labels {
+            let on_this_line = self
+                .text
+                .strip_prefix(label.as_str())
+                .is_some_and(|rest| rest.starts_with(':'));
+            if !on_this_line {
+                write!(f, "{}: ", label)?;
+            }
+        }
+        write!(f, "{}", self.text)
+    }
+}
+
+/// An assembled program along with a listing of which source line each instruction came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub code: Vec<Instruction>,
+    pub listing: Vec<ListingEntry>,
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.listing {
+            writeln!(f, "{:04} {:>5} | {}", entry.address, entry.line, entry.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// A word of source and the column it starts at
+type Word<'a> = (usize, &'a str);
+
+/// One line of source with its comment removed and any label and constant pulled out
+struct Line<'a> {
+    number: usize,
+    text: &'a str,
+    words: Vec<Word<'a>>,
+}
+
+/// Assemble a program written in the given instruction set. As well as one instruction
+/// per line, the source can have:
+/// - comments, from `#` or `;` to the end of the line
+/// - blank lines
+/// - labels such as `loop:`, which can start a line and be used as jump targets
+/// - constants such as `.const STEP -2`, which can be used anywhere a number can
+pub fn assemble(isa: &Isa, source: &str) -> Result<Program, AssemblyErrors> {
+    let mut errors = Vec::new();
+    let mut error = |line: usize, column: usize, message: String| {
+        errors.push(SourceError {
+            line,
+            column,
+            message,
+        })
+    };
+
+    // first pass finds where every label points and what every constant is
+    let mut labels = HashMap::new();
+    let mut constants = HashMap::new();
+    let mut instructions = Vec::new();
+    for (i, text) in source.lines().enumerate() {
+        let number = i + 1;
+        let text = text.split(['#', ';']).next().unwrap_or_default();
+        let mut words = words_with_columns(text);
+
+        if let Some((column, word)) = words.first().copied() {
+            if let Some(label) = word.strip_suffix(':') {
+                match check_name(isa, label, &labels, &constants) {
+                    Ok(()) => {
+                        labels.insert(label, instructions.len());
+                    }
+                    Err(message) => error(number, column, message),
+                }
+                words.remove(0);
+            }
+        }
+
+        match words.as_slice() {
+            [] => {}
+            [(column, ".const"), rest @ ..] => match rest {
+                [(name_column, name), (value_column, value)] => {
+                    match (check_name(isa, name, &labels, &constants), value.parse()) {
+                        (Ok(()), Ok(v)) => {
+                            constants.insert(*name, v);
+                        }
+                        (Err(message), _) => error(number, *name_column, message),
+                        (_, Err(_)) => error(
+                            number,
+                            *value_column,
+                            format!("'{}' is not a number", value),
+                        ),
+                    }
+                }
+                _ => error(number, *column, "Expected '.const NAME value'".to_owned()),
+            },
+            _ => instructions.push(Line {
+                number,
+                text: text.trim(),
+                words,
+            }),
+        }
+    }
+
+    // second pass builds each instruction with labels and constants filled in
+    let mut code = Vec::new();
+    let mut listing = Vec::new();
+    for (address, line) in instructions.iter().enumerate() {
+        let (column, mnemonic) = line.words[0];
+        let operands = &line.words[1..];
+        let opcode = match isa.opcode_index(mnemonic) {
+            Some(opcode) => opcode,
+            None => {
+                error(
+                    line.number,
+                    column,
+                    format!("Instruction '{}' not recognised", mnemonic),
+                );
+                continue;
+            }
+        };
+        let kinds = &isa.opcode(opcode).operands;
+        if kinds.len() != operands.len() {
+            error(
+                line.number,
+                column,
+                format!(
+                    "{} takes {} operands but was given {}",
+                    mnemonic,
+                    kinds.len(),
+                    operands.len()
+                ),
+            );
+            continue;
+        }
+
+        let mut resolved = Vec::new();
+        for (kind, (column, word)) in kinds.iter().zip(operands) {
+            let operand = if let Some(v) = constants.get(word) {
+                Ok(Operand::Number(*v))
+            } else if let Some(target) = labels.get(word) {
+                if *kind == OperandKind::Target {
+                    Ok(Operand::Number(*target as i32 - address as i32))
+                } else {
+                    Err(format!(
+                        "Label '{}' can only be used as a jump target",
+                        word
+                    ))
+                }
+            } else {
+                isa.operand(word)
+            };
+            match operand {
+                Ok(operand) if kind.accepts(&operand) => resolved.push(operand),
+                Ok(_) => error(
+                    line.number,
+                    *column,
+                    format!("'{}' can't be used as a {:?} operand", word, kind),
+                ),
+                Err(message) => error(line.number, *column, message),
+            }
+        }
+        if resolved.len() == kinds.len() {
+            code.push(Instruction {
+                opcode,
+                operands: resolved,
+            });
+            let mut labels = labels
+                .iter()
+                .filter(|(_, target)| **target == address)
+                .map(|(label, _)| label.to_string())
+                .collect::<Vec<_>>();
+            labels.sort();
+            listing.push(ListingEntry {
+                address,
+                line: line.number,
+                text: line.text.to_owned(),
+                labels,
+            });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(Program { code, listing })
+    } else {
+        // the first pass finds some errors later in the source than the second pass
+        errors.sort_by_key(|e| (e.line, e.column));
+        Err(AssemblyErrors(errors))
+    }
+}
+
+fn words_with_columns(text: &str) -> Vec<Word<'_>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(s), true) => {
+                words.push((text[..s].chars().count() + 1, &text[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+fn check_name(
+    isa: &Isa,
+    name: &str,
+    labels: &HashMap<&str, usize>,
+    constants: &HashMap<&str, i32>,
+) -> Result<(), String> {
+    if name.is_empty() || name.parse::<i32>().is_ok() {
+        Err(format!("'{}' can't be used as a name", name))
+    } else if isa.register_index(name).is_some() {
+        Err(format!("'{}' is already the name of a register", name))
+    } else if labels.contains_key(name) || constants.contains_key(name) {
+        Err(format!("'{}' is defined more than once", name))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn counter_isa() -> Isa {
+    "registers: a b
+inc r v: cycles 1, add $0 $1
+jnz v t: cycles 2, jump-if != $0 0 $1"
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn test_labels_constants_and_listing() {
+    let source = "; count down
+.const START 3
+
+        inc a START
+loop:   inc a -1   # one less
+        inc b 2
+        jnz a loop
+";
+    let program = assemble(&counter_isa(), source).unwrap();
+    let isa = counter_isa();
+    let disassembled = program
+        .code
+        .iter()
+        .map(|i| isa.disassemble(i))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        disassembled,
+        vec!["inc a 3", "inc a -1", "inc b 2", "jnz a -2"]
+    );
+    assert_eq!(program.listing[3].line, 7);
+    assert_eq!(program.listing[1].text, "loop:   inc a -1");
+    assert_eq!(
+        program.to_string().lines().nth(1),
+        Some("0001     5 | loop:   inc a -1")
+    );
+    assert_eq!(program.listing[1].labels, vec!["loop"]);
+    assert_eq!(program.listing[1].to_string(), "line 5: loop:   inc a -1");
+    assert_eq!(program.listing[2].to_string(), "line 6: inc b 2");
+}
+
+#[test]
+fn test_label_on_its_own_line() {
+    let program = assemble(&counter_isa(), "top:\n\n  inc a 1\n  jnz a top").unwrap();
+    assert_eq!(program.listing[0].labels, vec!["top"]);
+    assert_eq!(program.listing[0].to_string(), "line 3: top: inc a 1");
+}
+
+#[test]
+fn test_collects_every_error() {
+    let source = "inc a 1
+  frob a
+inc a
+inc 3 1
+a: inc a 1
+jnz a nowhere
+.const X";
+    let errors = assemble(&counter_isa(), source).unwrap_err();
+    assert_eq!(
+        errors.to_string(),
+        "2:3: Instruction 'frob' not recognised
+3:1: inc takes 2 operands but was given 1
+4:5: '3' can't be used as a Register operand
+5:1: 'a' is already the name of a register
+6:7: 'nowhere' is neither a register nor a number
+7:1: Expected '.const NAME value'"
+    );
+}
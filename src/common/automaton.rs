@@ -0,0 +1,396 @@
+use super::grid::Grid;
+use rayon::prelude::*;
+#[cfg(test)]
+use std::collections::BTreeSet;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+/// Which surrounding cells count as neighbours
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// The four orthogonally adjacent cells
+    VonNeumann,
+    /// All eight surrounding cells, including diagonals
+    Moore,
+}
+
+#[cfg(test)]
+impl Neighbourhood {
+    pub fn offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            Neighbourhood::VonNeumann => &[(0, -1), (-1, 0), (1, 0), (0, 1)],
+            Neighbourhood::Moore => &[
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ],
+        }
+    }
+}
+
+/// A view of one cell of a grid and the cells around it, handed to automaton rules
+pub struct Cell<'a, T> {
+    grid: &'a Grid<T>,
+    x: usize,
+    y: usize,
+}
+
+impl<'a, T> Cell<'a, T> {
+    pub fn value(&self) -> &'a T {
+        self.grid
+            .get(self.x, self.y)
+            .expect("cells are only made for coordinates inside the grid")
+    }
+
+    /// The cell at the given offset from this one, if it's inside the grid
+    pub fn offset(&self, dx: isize, dy: isize) -> Option<&'a T> {
+        let x = self.x.checked_add_signed(dx)?;
+        let y = self.y.checked_add_signed(dy)?;
+        self.grid.get(x, y).ok()
+    }
+
+    /// The neighbouring cells which are inside the grid
+    #[cfg(test)]
+    pub fn neighbours(&self, kind: Neighbourhood) -> impl Iterator<Item = &'a T> + '_ {
+        kind.offsets()
+            .iter()
+            .filter_map(|(dx, dy)| self.offset(*dx, *dy))
+    }
+
+    #[cfg(test)]
+    pub fn count_neighbours(&self, kind: Neighbourhood, predicate: impl Fn(&T) -> bool) -> usize {
+        self.neighbours(kind).filter(|t| predicate(t)).count()
+    }
+}
+
+/// How a run of an automaton finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// A step at this generation changed nothing, so no later step will either
+    Stable(usize),
+    /// The state at generation start + length was the same as the one at start
+    Cycle { start: usize, length: usize },
+    /// Ran out of steps before anything else happened
+    StepLimit(usize),
+}
+
+/// Something which can be stepped forward a generation at a time
+pub trait Automaton {
+    /// Advance one generation, returning true if anything changed
+    fn step(&mut self) -> bool;
+
+    fn generation(&self) -> usize;
+
+    /// A hash of the current state, used to spot cycles. A hash collision
+    /// could report a cycle which isn't there, but with 64 bits it won't.
+    fn state_hash(&self) -> u64;
+
+    /// Step until the automaton stops changing, starts repeating a previous
+    /// state or max_steps steps have been taken
+    fn run(&mut self, max_steps: usize, detect_cycles: bool) -> Outcome {
+        let mut seen = HashMap::new();
+        if detect_cycles {
+            seen.insert(self.state_hash(), self.generation());
+        }
+        for _ in 0..max_steps {
+            if !self.step() {
+                return Outcome::Stable(self.generation());
+            }
+            if detect_cycles {
+                if let Some(start) = seen.insert(self.state_hash(), self.generation()) {
+                    return Outcome::Cycle {
+                        start,
+                        length: self.generation() - start,
+                    };
+                }
+            }
+        }
+        Outcome::StepLimit(self.generation())
+    }
+}
+
+fn hash_of(t: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    t.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A dense automaton on a Grid, where the rule decides each cell's next value from
+/// its current surroundings. Two grids are kept and swapped each step so that
+/// every cell sees the previous generation.
+pub struct GridAutomaton<T, R> {
+    current: Grid<T>,
+    next: Grid<T>,
+    rule: R,
+    generation: usize,
+    parallel: bool,
+}
+
+impl<T, R> GridAutomaton<T, R>
+where
+    T: Clone + PartialEq + Hash + Send + Sync,
+    R: Fn(&Cell<T>) -> T + Sync,
+{
+    pub fn new(grid: Grid<T>, rule: R) -> Self {
+        Self {
+            next: grid.clone(),
+            current: grid,
+            rule,
+            generation: 0,
+            parallel: false,
+        }
+    }
+
+    /// Compute rows of each generation in parallel
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn grid(&self) -> &Grid<T> {
+        &self.current
+    }
+
+    pub fn into_grid(self) -> Grid<T> {
+        self.current
+    }
+}
+
+impl<T, R> Automaton for GridAutomaton<T, R>
+where
+    T: Clone + PartialEq + Hash + Send + Sync,
+    R: Fn(&Cell<T>) -> T + Sync,
+{
+    fn step(&mut self) -> bool {
+        let current = &self.current;
+        let rule = &self.rule;
+        let fill_row = |(y, row): (usize, &mut [T])| {
+            let mut changed = false;
+            for (x, cell) in row.iter_mut().enumerate() {
+                let value = rule(&Cell {
+                    grid: current,
+                    x,
+                    y,
+                });
+                changed |= value != *current.get(x, y).expect("same size as next");
+                *cell = value;
+            }
+            changed
+        };
+
+        let changed = if self.parallel {
+            self.next
+                .rows_mut()
+                .enumerate()
+                .collect::<Vec<_>>()
+                .into_par_iter()
+                .map(fill_row)
+                .reduce(|| false, |a, b| a || b)
+        } else {
+            self.next
+                .rows_mut()
+                .enumerate()
+                .map(fill_row)
+                // count rather than any, so every row is filled in
+                .filter(|changed| *changed)
+                .count()
+                > 0
+        };
+
+        std::mem::swap(&mut self.current, &mut self.next);
+        self.generation += 1;
+        changed
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn state_hash(&self) -> u64 {
+        hash_of(&self.current)
+    }
+}
+
+/// A life-like automaton on an unbounded plane, storing only the live cells.
+/// The rule is given whether a cell is alive and how many live neighbours it has.
+#[cfg(test)]
+pub struct SparseAutomaton<R> {
+    live: BTreeSet<(i64, i64)>,
+    neighbourhood: Neighbourhood,
+    rule: R,
+    generation: usize,
+}
+
+#[cfg(test)]
+impl<R> SparseAutomaton<R>
+where
+    R: Fn(bool, usize) -> bool,
+{
+    pub fn new(
+        live: impl IntoIterator<Item = (i64, i64)>,
+        neighbourhood: Neighbourhood,
+        rule: R,
+    ) -> Self {
+        Self {
+            live: live.into_iter().collect(),
+            neighbourhood,
+            rule,
+            generation: 0,
+        }
+    }
+
+    pub fn live_cells(&self) -> &BTreeSet<(i64, i64)> {
+        &self.live
+    }
+
+    fn neighbours_of(&self, (x, y): (i64, i64)) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.neighbourhood
+            .offsets()
+            .iter()
+            .map(move |(dx, dy)| (x + *dx as i64, y + *dy as i64))
+    }
+}
+
+#[cfg(test)]
+impl<R> Automaton for SparseAutomaton<R>
+where
+    R: Fn(bool, usize) -> bool,
+{
+    fn step(&mut self) -> bool {
+        // only live cells and their neighbours can possibly be alive next time
+        let candidates = self
+            .live
+            .iter()
+            .flat_map(|p| std::iter::once(*p).chain(self.neighbours_of(*p)))
+            .collect::<BTreeSet<_>>();
+        let next = candidates
+            .into_iter()
+            .filter(|p| {
+                let live_neighbours = self
+                    .neighbours_of(*p)
+                    .filter(|n| self.live.contains(n))
+                    .count();
+                (self.rule)(self.live.contains(p), live_neighbours)
+            })
+            .collect::<BTreeSet<_>>();
+
+        let changed = next != self.live;
+        self.live = next;
+        self.generation += 1;
+        changed
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn state_hash(&self) -> u64 {
+        hash_of(&self.live)
+    }
+}
+
+/// Conway's Game of Life, handy for testing and as an example rule
+#[cfg(test)]
+fn life(alive: bool, live_neighbours: usize) -> bool {
+    matches!((alive, live_neighbours), (true, 2) | (_, 3))
+}
+
+#[cfg(test)]
+fn life_on_grid(cell: &Cell<bool>) -> bool {
+    life(
+        *cell.value(),
+        cell.count_neighbours(Neighbourhood::Moore, |c| *c),
+    )
+}
+
+#[cfg(test)]
+fn blinker() -> Grid<bool> {
+    let mut grid = Grid::new(5, 5);
+    for x in 1..=3 {
+        grid.set(x, 2, true).unwrap();
+    }
+    grid
+}
+
+#[test]
+fn test_grid_blinker_cycles() {
+    let mut automaton = GridAutomaton::new(blinker(), life_on_grid);
+    assert!(automaton.step());
+    assert!(*automaton.grid().get(2, 1).unwrap());
+    assert!(!*automaton.grid().get(1, 2).unwrap());
+    assert_eq!(
+        automaton.run(10, true),
+        Outcome::Cycle {
+            start: 1,
+            length: 2
+        }
+    );
+}
+
+#[test]
+fn test_grid_parallel_matches_sequential() {
+    let mut sequential = GridAutomaton::new(blinker(), life_on_grid);
+    let mut parallel = GridAutomaton::new(blinker(), life_on_grid).parallel(true);
+    for _ in 0..3 {
+        sequential.step();
+        parallel.step();
+        assert_eq!(sequential.grid(), parallel.grid());
+    }
+}
+
+#[test]
+fn test_grid_becomes_stable() {
+    // a block is a still life, so the first step changes nothing
+    let mut grid = Grid::new(4, 4);
+    for (x, y) in [(1, 1), (2, 1), (1, 2), (2, 2)] {
+        grid.set(x, y, true).unwrap();
+    }
+    let mut automaton = GridAutomaton::new(grid, life_on_grid);
+    assert_eq!(automaton.run(10, false), Outcome::Stable(1));
+}
+
+#[test]
+fn test_sparse_glider() {
+    let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+    let mut automaton = SparseAutomaton::new(glider, Neighbourhood::Moore, life);
+    for _ in 0..4 {
+        automaton.step();
+    }
+    // after four generations a glider has moved one cell diagonally
+    assert_eq!(
+        automaton.live_cells(),
+        &glider.iter().map(|(x, y)| (x + 1, y + 1)).collect()
+    );
+    assert_eq!(automaton.run(20, false), Outcome::StepLimit(24));
+}
+
+#[test]
+fn test_neighbourhoods() {
+    let grid = blinker();
+    let centre = Cell {
+        grid: &grid,
+        x: 2,
+        y: 2,
+    };
+    assert_eq!(
+        centre.count_neighbours(Neighbourhood::VonNeumann, |c| *c),
+        2
+    );
+    assert_eq!(centre.count_neighbours(Neighbourhood::Moore, |c| *c), 2);
+    let corner = Cell {
+        grid: &grid,
+        x: 0,
+        y: 0,
+    };
+    assert_eq!(corner.neighbours(Neighbourhood::VonNeumann).count(), 2);
+    assert_eq!(corner.neighbours(Neighbourhood::Moore).count(), 3);
+}
@@ -0,0 +1,405 @@
+use super::display::Display;
+use super::grid::{Grid, GridOperationError};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+const WORD_BITS: usize = 64;
+
+/// A grid of booleans packed one bit per cell into u64 words, with each row
+/// starting on a fresh word. Bits past the width of a row are always zero, so
+/// equality and hashing only need to look at the words.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "BitGridData")]
+pub struct BitGrid {
+    words: Vec<u64>,
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(WORD_BITS);
+        Self {
+            words: vec![0; words_per_row * height],
+            width,
+            height,
+            words_per_row,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: bool) -> Result<(), GridOperationError> {
+        let (word, bit) = self.index_of(x, y)?;
+        if value {
+            self.words[word] |= bit;
+        } else {
+            self.words[word] &= !bit;
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Result<bool, GridOperationError> {
+        let (word, bit) = self.index_of(x, y)?;
+        Ok(self.words[word] & bit != 0)
+    }
+
+    pub fn iter_coords(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| (x, y)))
+    }
+
+    /// Coordinates of every set cell, in the same order as iter_coords
+    pub fn iter_set(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.iter_coords()
+            .filter(|(x, y)| self.get(*x, *y).unwrap_or(false))
+    }
+
+    /// The packed words making up row y
+    pub fn row(&self, y: usize) -> Result<&[u64], GridOperationError> {
+        let words = self.row_words(y)?;
+        Ok(&self.words[words])
+    }
+
+    /// Number of set cells in the whole grid
+    #[cfg(test)]
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Number of set cells in row y
+    pub fn count_ones_in_row(&self, y: usize) -> Result<usize, GridOperationError> {
+        Ok(self.row(y)?.iter().map(|w| w.count_ones() as usize).sum())
+    }
+
+    pub fn is_row_full(&self, y: usize) -> Result<bool, GridOperationError> {
+        Ok(self.count_ones_in_row(y)? == self.width)
+    }
+
+    #[cfg(test)]
+    pub fn is_row_empty(&self, y: usize) -> Result<bool, GridOperationError> {
+        Ok(self.row(y)?.iter().all(|w| *w == 0))
+    }
+
+    /// Combine row y with a packed row of bits using op, keeping bits past the width clear
+    #[cfg(test)]
+    fn combine_row(
+        &mut self,
+        y: usize,
+        bits: &[u64],
+        op: impl Fn(u64, u64) -> u64,
+    ) -> Result<(), GridOperationError> {
+        let words = self.row_words(y)?;
+        for (word, b) in self.words[words].iter_mut().zip(bits.iter()) {
+            *word = op(*word, *b);
+        }
+        self.mask_row(y);
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub fn and_row(&mut self, y: usize, bits: &[u64]) -> Result<(), GridOperationError> {
+        // missing words in bits count as zero
+        let words = self.row_words(y)?;
+        for word in self.words[words].iter_mut().skip(bits.len()) {
+            *word = 0;
+        }
+        self.combine_row(y, bits, |a, b| a & b)
+    }
+
+    #[cfg(test)]
+    pub fn or_row(&mut self, y: usize, bits: &[u64]) -> Result<(), GridOperationError> {
+        self.combine_row(y, bits, |a, b| a | b)
+    }
+
+    #[cfg(test)]
+    pub fn xor_row(&mut self, y: usize, bits: &[u64]) -> Result<(), GridOperationError> {
+        self.combine_row(y, bits, |a, b| a ^ b)
+    }
+
+    /// True if any cell is set in both this grid and other, which must be the same size
+    #[cfg(test)]
+    pub fn intersects(&self, other: &BitGrid) -> Result<bool, GridOperationError> {
+        if self.width != other.width || self.height != other.height {
+            return Err(GridOperationError::SizeMismatch(
+                self.width,
+                self.height,
+                other.width,
+                other.height,
+            ));
+        }
+        Ok(self
+            .words
+            .iter()
+            .zip(other.words.iter())
+            .any(|(a, b)| a & b != 0))
+    }
+
+    /// Move every cell n places towards x = 0, dropping cells that fall off the edge
+    #[cfg(test)]
+    pub fn shift_left(&mut self, n: usize) {
+        for y in 0..self.height {
+            let start = y * self.words_per_row;
+            shift_towards_low_bits(&mut self.words[start..start + self.words_per_row], n);
+        }
+    }
+
+    /// Move every cell n places away from x = 0, dropping cells that fall off the edge
+    #[cfg(test)]
+    pub fn shift_right(&mut self, n: usize) {
+        for y in 0..self.height {
+            let start = y * self.words_per_row;
+            shift_towards_high_bits(&mut self.words[start..start + self.words_per_row], n);
+            self.mask_row(y);
+        }
+    }
+
+    /// Add empty rows to the bottom (highest y) of the grid
+    pub fn push_empty_rows(&mut self, count: usize) {
+        self.words
+            .extend(std::iter::repeat_n(0, count * self.words_per_row));
+        self.height += count;
+    }
+
+    /// Remove a range of rows, moving any rows after them up to fill the gap
+    pub fn remove_rows(&mut self, rows: Range<usize>) -> Result<(), GridOperationError> {
+        if rows.end > self.height {
+            return Err(GridOperationError::IndexOutOfBounds(
+                0,
+                rows.end - 1,
+                self.width,
+                self.height,
+            ));
+        }
+        let removed = rows.len();
+        self.words
+            .drain(rows.start * self.words_per_row..rows.end * self.words_per_row);
+        self.height -= removed;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub fn to_grid(&self) -> Grid<bool> {
+        let mut grid = Grid::new(self.width, self.height);
+        for (x, y) in self.iter_set() {
+            grid.set(x, y, true)
+                .expect("grids are the same size so coordinates are valid");
+        }
+        grid
+    }
+
+    fn mask_row(&mut self, y: usize) {
+        let spare_bits = self.words_per_row * WORD_BITS - self.width;
+        if spare_bits > 0 {
+            let last = (y + 1) * self.words_per_row - 1;
+            self.words[last] &= u64::MAX >> spare_bits;
+        }
+    }
+
+    /// The range of words holding row y
+    fn row_words(&self, y: usize) -> Result<Range<usize>, GridOperationError> {
+        if y >= self.height {
+            Err(GridOperationError::IndexOutOfBounds(
+                0,
+                y,
+                self.width,
+                self.height,
+            ))
+        } else {
+            let start = y * self.words_per_row;
+            Ok(start..start + self.words_per_row)
+        }
+    }
+
+    fn index_of(&self, x: usize, y: usize) -> Result<(usize, u64), GridOperationError> {
+        if x >= self.width || y >= self.height {
+            Err(GridOperationError::IndexOutOfBounds(
+                x,
+                y,
+                self.width,
+                self.height,
+            ))
+        } else {
+            Ok((y * self.words_per_row + x / WORD_BITS, 1 << (x % WORD_BITS)))
+        }
+    }
+}
+
+#[cfg(test)]
+fn shift_towards_low_bits(row: &mut [u64], n: usize) {
+    let (word_shift, bit_shift) = (n / WORD_BITS, n % WORD_BITS);
+    for i in 0..row.len() {
+        let src = i + word_shift;
+        let mut word = row.get(src).map(|w| w >> bit_shift).unwrap_or(0);
+        if bit_shift > 0 {
+            word |= row
+                .get(src + 1)
+                .map(|w| w << (WORD_BITS - bit_shift))
+                .unwrap_or(0);
+        }
+        row[i] = word;
+    }
+}
+
+#[cfg(test)]
+fn shift_towards_high_bits(row: &mut [u64], n: usize) {
+    let (word_shift, bit_shift) = (n / WORD_BITS, n % WORD_BITS);
+    for i in (0..row.len()).rev() {
+        let mut word = 0;
+        if i >= word_shift {
+            let src = i - word_shift;
+            word = row[src] << bit_shift;
+            if bit_shift > 0 && src > 0 {
+                word |= row[src - 1] >> (WORD_BITS - bit_shift);
+            }
+        }
+        row[i] = word;
+    }
+}
+
+/// The fields of a BitGrid as they're stored, checked before becoming one
+#[derive(Deserialize)]
+struct BitGridData {
+    words: Vec<u64>,
+    width: usize,
+    height: usize,
+}
+
+impl TryFrom<BitGridData> for BitGrid {
+    type Error = String;
+
+    fn try_from(data: BitGridData) -> Result<Self, Self::Error> {
+        let mut bits = BitGrid::new(data.width, data.height);
+        if data.words.len() != bits.words.len() {
+            return Err(format!(
+                "A {}x{} bit grid needs {} words but there are {}",
+                data.width,
+                data.height,
+                bits.words.len(),
+                data.words.len()
+            ));
+        }
+        bits.words = data.words;
+        for y in 0..bits.height {
+            bits.mask_row(y);
+        }
+        Ok(bits)
+    }
+}
+
+impl From<&Grid<bool>> for BitGrid {
+    fn from(grid: &Grid<bool>) -> Self {
+        let mut bits = BitGrid::new(grid.width(), grid.height());
+        for (x, y) in grid.iter_coords() {
+            if *grid.get(x, y).expect("coordinates come from the grid") {
+                bits.set(x, y, true)
+                    .expect("grids are the same size so coordinates are valid");
+            }
+        }
+        bits
+    }
+}
+
+impl From<&BitGrid> for Display {
+    fn from(bits: &BitGrid) -> Self {
+        let mut display = Display::new(bits.width(), bits.height());
+        for (x, y) in bits.iter_set() {
+            display
+                .set(x, y, true)
+                .expect("display is the same size so coordinates are valid");
+        }
+        display
+    }
+}
+
+#[test]
+fn test_set_get_count() {
+    let mut bits = BitGrid::new(70, 3);
+    bits.set(0, 0, true).unwrap();
+    bits.set(69, 0, true).unwrap();
+    bits.set(64, 2, true).unwrap();
+    assert!(bits.get(69, 0).unwrap());
+    assert!(!bits.get(68, 0).unwrap());
+    assert!(bits.get(70, 0).is_err());
+    assert_eq!(bits.count_ones(), 3);
+    assert_eq!(bits.count_ones_in_row(0).unwrap(), 2);
+    assert!(bits.is_row_empty(1).unwrap());
+    bits.set(0, 0, false).unwrap();
+    assert_eq!(bits.iter_set().collect::<Vec<_>>(), vec![(69, 0), (64, 2)]);
+}
+
+#[test]
+fn test_shifts_cross_words() {
+    let mut bits = BitGrid::new(70, 1);
+    bits.set(62, 0, true).unwrap();
+    bits.set(68, 0, true).unwrap();
+    bits.shift_right(3);
+    // 68 falls off the end, 62 crosses into the second word
+    assert_eq!(bits.iter_set().collect::<Vec<_>>(), vec![(65, 0)]);
+    bits.shift_left(65);
+    assert_eq!(bits.iter_set().collect::<Vec<_>>(), vec![(0, 0)]);
+    bits.shift_left(1);
+    assert_eq!(bits.count_ones(), 0);
+}
+
+#[test]
+fn test_row_ops_and_growth() {
+    let mut bits = BitGrid::new(7, 1);
+    bits.or_row(0, &[0b1111111]).unwrap();
+    assert!(bits.is_row_full(0).unwrap());
+    bits.xor_row(0, &[0b1010101]).unwrap();
+    assert_eq!(bits.row(0).unwrap(), &[0b0101010]);
+    bits.and_row(0, &[0b0000110]).unwrap();
+    assert_eq!(bits.row(0).unwrap(), &[0b0000010]);
+    bits.or_row(0, &[u64::MAX]).unwrap();
+    assert_eq!(bits.count_ones(), 7, "bits past the width stay clear");
+
+    bits.push_empty_rows(2);
+    bits.set(3, 2, true).unwrap();
+    bits.remove_rows(0..2).unwrap();
+    assert_eq!(bits.height(), 1);
+    assert_eq!(bits.iter_set().collect::<Vec<_>>(), vec![(3, 0)]);
+}
+
+#[test]
+fn test_grid_conversion() {
+    let mut grid = Grid::new(3, 2);
+    grid.set(2, 1, true).unwrap();
+    let bits = BitGrid::from(&grid);
+    assert!(bits.get(2, 1).unwrap());
+    assert_eq!(Display::from(&bits).to_string(), "...\n..#\n");
+    assert_eq!(BitGrid::from(&bits.to_grid()), bits);
+
+    let mut other = BitGrid::new(3, 2);
+    assert!(!bits.intersects(&other).unwrap());
+    other.set(2, 1, true).unwrap();
+    assert!(bits.intersects(&other).unwrap());
+}
+
+#[test]
+fn test_rows_out_of_range() {
+    let mut bits = BitGrid::new(7, 2);
+    assert!(bits.row(2).is_err());
+    assert!(bits.is_row_full(2).is_err());
+    assert!(bits.is_row_empty(5).is_err());
+    assert!(bits.or_row(2, &[1]).is_err());
+    assert!(bits.remove_rows(1..3).is_err());
+    assert_eq!(
+        bits.height(),
+        2,
+        "a failed removal leaves every row in place"
+    );
+}
+
+#[test]
+fn test_intersects_needs_same_size() {
+    let bits = BitGrid::new(7, 2);
+    assert!(bits.intersects(&BitGrid::new(8, 2)).is_err());
+    assert!(bits.intersects(&BitGrid::new(7, 3)).is_err());
+}
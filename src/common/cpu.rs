@@ -1,60 +1,90 @@
-use std::{error::Error, str::FromStr};
+use super::assembler::{assemble, ListingEntry};
+use super::isa::{Effect, Instruction, Isa, Operand, RegisterRef, Value};
+use super::peripheral::Peripheral;
+use std::{cell::RefCell, error::Error, fmt, rc::Rc};
 
-#[derive(Debug)]
 pub struct Cpu {
+    isa: Isa,
     code: Vec<Instruction>,
+    /// Where each instruction came from, if the Cpu compiled it from source
+    listing: Vec<ListingEntry>,
     pc: usize,
     cycle: usize,
-    x: i32,
+    registers: Vec<i32>,
     state: State,
+    devices: Vec<Rc<RefCell<dyn Peripheral>>>,
+}
+
+impl fmt::Debug for Cpu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cpu")
+            .field("isa", &self.isa)
+            .field("code", &self.code)
+            .field("listing", &self.listing)
+            .field("pc", &self.pc)
+            .field("cycle", &self.cycle)
+            .field("registers", &self.registers)
+            .field("state", &self.state)
+            .field("devices", &self.devices.len())
+            .finish()
+    }
 }
 
 impl Cpu {
+    /// Compile a program for the handheld device
     pub fn compile(source: &str) -> Result<Cpu, Box<dyn Error>> {
-        let code = source
-            .lines()
-            .map(Instruction::from_str)
-            .collect::<Result<Vec<Instruction>, _>>()?;
-        Ok(Cpu {
+        Self::compile_for(Isa::handheld(), source)
+    }
+
+    /// Compile a program written in the given instruction set; see assembler::assemble
+    /// for what the source can contain
+    pub fn compile_for(isa: Isa, source: &str) -> Result<Cpu, Box<dyn Error>> {
+        let program = assemble(&isa, source)?;
+        let mut cpu = Self::load(isa, program.code);
+        cpu.listing = program.listing;
+        Ok(cpu)
+    }
+
+    /// A Cpu ready to run already compiled code
+    pub fn load(isa: Isa, code: Vec<Instruction>) -> Cpu {
+        Cpu {
+            registers: isa.initial_values().to_vec(),
+            isa,
             code,
+            listing: Vec::new(),
             pc: 0,
             cycle: 1,
-            x: 1,
             state: State::BeginNextInstruction,
-        })
+            devices: Vec::new(),
+        }
+    }
+
+    /// Attach a device which will be shown the registers during every cycle from now on.
+    /// Keep a clone of the Rc to look at what the device did afterwards.
+    pub fn attach(&mut self, device: Rc<RefCell<dyn Peripheral>>) {
+        self.devices.push(device);
     }
 
     /// Run the next cycle, returning the value of X during (not after) the cycle
     pub fn cycle(&mut self) -> i32 {
-        let x = self.x;
+        let x = self.get_x();
+        if self.state != State::Complete {
+            for device in &self.devices {
+                device.borrow_mut().on_cycle(self.cycle, &self.registers);
+            }
+        }
         match self.state {
-            State::BeginNextInstruction => match self.code[self.pc] {
-                Instruction::AddX(_) => self.state = State::MidInstruction(1),
-                Instruction::Noop => {
-                    self.pc += 1;
-                    if self.pc == self.code.len() {
-                        self.state = State::Complete;
-                    } else {
-                        self.state = State::BeginNextInstruction
-                    }
+            State::BeginNextInstruction => {
+                let cycles = self.isa.opcode(self.code[self.pc].opcode).cycles;
+                if cycles > 1 {
+                    self.state = State::MidInstruction(cycles - 1);
+                } else {
+                    self.execute();
                 }
-            },
+            }
             State::MidInstruction(remaining_cycles) => {
                 if remaining_cycles == 1 {
-                    // finish the current instruction
-                    self.state = State::BeginNextInstruction;
-                    match self.code[self.pc] {
-                        Instruction::AddX(v) => self.x += v,
-                        Instruction::Noop => {}
-                    }
-
-                    // prepare for the next instruction
-                    self.pc += 1;
-                    if self.pc == self.code.len() {
-                        self.state = State::Complete;
-                    } else {
-                        self.state = State::BeginNextInstruction
-                    }
+                    self.execute();
                 } else {
                     // count down for this instruction to finish
                     self.state = State::MidInstruction(remaining_cycles - 1);
@@ -66,14 +96,132 @@ impl Cpu {
         x
     }
 
+    /// Apply the effect of the current instruction and move on to the next one
+    fn execute(&mut self) {
+        let instruction = &self.code[self.pc];
+        let value = |v: Value| match v {
+            Value::Literal(n) => n,
+            Value::Register(r) => self.registers[r],
+            Value::Operand(i) => match instruction.operands[i] {
+                Operand::Register(r) => self.registers[r],
+                Operand::Number(n) => n,
+            },
+        };
+        let register = |r: RegisterRef| match r {
+            RegisterRef::Fixed(r) => r,
+            RegisterRef::Operand(i) => match instruction.operands[i] {
+                Operand::Register(r) => r,
+                Operand::Number(_) => unreachable!("the isa checks written operands are registers"),
+            },
+        };
+
+        let mut jump = 1;
+        match self.isa.opcode(instruction.opcode).effect {
+            Effect::Nop => {}
+            Effect::Set(r, v) => {
+                let v = value(v);
+                self.registers[register(r)] = v;
+            }
+            Effect::Add(r, v) => {
+                let v = value(v);
+                let r = register(r);
+                // registers wrap rather than overflow, like a real machine's
+                self.registers[r] = self.registers[r].wrapping_add(v);
+            }
+            Effect::Mul(r, v) => {
+                let v = value(v);
+                let r = register(r);
+                self.registers[r] = self.registers[r].wrapping_mul(v);
+            }
+            Effect::Jump(v) => jump = value(v),
+            Effect::JumpIf(cmp, a, b, v) => {
+                if cmp.holds(value(a), value(b)) {
+                    jump = value(v);
+                }
+            }
+        }
+
+        // prepare for the next instruction, finishing if we jump outside the program
+        match self.pc.checked_add_signed(jump as isize) {
+            Some(pc) if pc < self.code.len() => {
+                self.pc = pc;
+                self.state = State::BeginNextInstruction;
+            }
+            _ => {
+                self.pc = self.code.len();
+                self.state = State::Complete;
+            }
+        }
+    }
+
+    /// The first register, which is X on the handheld device
     pub fn get_x(&self) -> i32 {
-        self.x
+        self.registers[0]
+    }
+
+    #[cfg(test)]
+    pub fn register(&self, name: &str) -> Option<i32> {
+        self.isa.register_index(name).map(|r| self.registers[r])
+    }
+
+    pub fn registers(&self) -> &[i32] {
+        &self.registers
+    }
+
+    pub fn isa(&self) -> &Isa {
+        &self.isa
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// The number of the cycle which will run next, starting from 1
+    pub fn current_cycle(&self) -> usize {
+        self.cycle
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// The instruction at the program counter, or None once the program has finished
+    pub fn current_instruction(&self) -> Option<&Instruction> {
+        self.code.get(self.pc)
+    }
+
+    /// The instruction at the program counter as it would be written in source
+    pub fn disassemble_current(&self) -> Option<String> {
+        self.current_instruction().map(|i| self.isa.disassemble(i))
+    }
+
+    /// Where the instruction at address was written, if the Cpu compiled it from source
+    pub fn source(&self, address: usize) -> Option<&ListingEntry> {
+        self.listing.get(address)
     }
 
     pub fn is_complete(&self) -> bool {
         self.pc >= self.code.len()
     }
 
+    /// Everything about the Cpu apart from its program, for going back to later
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            pc: self.pc,
+            cycle: self.cycle,
+            registers: self.registers.clone(),
+            state: self.state,
+        }
+    }
+
+    /// Put the Cpu back how it was when the snapshot was taken
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.pc = snapshot.pc;
+        self.cycle = snapshot.cycle;
+        self.registers.clone_from(&snapshot.registers);
+        self.state = snapshot.state;
+    }
+
     /// Runs the program to completion, returning a vector containing the value of X at every cycle
     pub fn run_to_completion(&mut self) -> Vec<i32> {
         let mut result = Vec::new();
@@ -84,28 +232,35 @@ impl Cpu {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
-enum Instruction {
-    AddX(i32),
-    Noop,
+/// The registers and state of a Cpu at some point while running its program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pc: usize,
+    cycle: usize,
+    registers: Vec<i32>,
+    state: State,
 }
 
-impl FromStr for Instruction {
-    type Err = Box<dyn Error>;
+impl Snapshot {
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Some(valstr) = s.trim().strip_prefix("addx ") {
-            Ok(Instruction::AddX(valstr.parse()?))
-        } else if s.trim() == "noop" {
-            Ok(Instruction::Noop)
-        } else {
-            Err(format!("Input instruction '{}' not recognised", s).into())
-        }
+    pub fn cycle(&self) -> usize {
+        self.cycle
+    }
+
+    pub fn registers(&self) -> &[i32] {
+        &self.registers
+    }
+
+    pub fn state(&self) -> State {
+        self.state
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum State {
+pub enum State {
     /// Currently executing instruction at program counter, with the indicated number of cycles to go
     MidInstruction(usize),
     /// Next cycle will begin execution of instruction at pc
@@ -127,3 +282,65 @@ addx -5";
     assert_eq!(cpu.cycle(), 4);
     assert_eq!(cpu.get_x(), -1);
 }
+
+#[test]
+fn test_jumps_and_registers() {
+    let isa = "# count a down from 5, adding 3 to b each time
+registers: a=5 b
+inc r v: cycles 1, add $0 $1
+mul r v: cycles 3, mul $0 $1
+jnz v t: cycles 2, jump-if != $0 0 $1"
+        .parse()
+        .unwrap();
+    let program = "inc b 3
+inc a -1
+jnz a -2
+mul b b";
+    let mut cpu = Cpu::compile_for(isa, program).unwrap();
+    let cycles = cpu.run_to_completion().len();
+    assert_eq!(cpu.register("a"), Some(0));
+    assert_eq!(cpu.register("b"), Some(225));
+    assert_eq!(cycles, 5 * 4 + 3);
+}
+
+#[test]
+fn test_arithmetic_wraps() {
+    let isa = "registers: x
+inc r v: cycles 1, add $0 $1
+mul r v: cycles 1, mul $0 $1"
+        .parse()
+        .unwrap();
+    let program = "inc x 2147483646
+mul x 2
+inc x 3";
+    let mut cpu = Cpu::compile_for(isa, program).unwrap();
+    cpu.run_to_completion();
+    // (i32::MAX - 1) * 2 wraps round to -4, then adding 3 gives -1
+    assert_eq!(cpu.register("x"), Some(-1));
+}
+
+#[test]
+fn test_jump_out_of_program_completes() {
+    let isa = "registers: x
+jmp t: cycles 1, jump $0"
+        .parse()
+        .unwrap();
+    let mut cpu = Cpu::compile_for(isa, "jmp -1").unwrap();
+    cpu.cycle();
+    assert!(cpu.is_complete());
+    assert_eq!(cpu.state(), State::Complete);
+}
+
+#[test]
+fn test_attached_devices_see_every_cycle() {
+    use super::peripheral::SignalSampler;
+
+    let mut cpu = Cpu::compile("noop\naddx 3\naddx -5").unwrap();
+    let sampler = Rc::new(RefCell::new(SignalSampler::new(1..=10)));
+    cpu.attach(sampler.clone());
+    cpu.run_to_completion();
+    assert_eq!(
+        sampler.borrow().samples(),
+        &[(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]
+    );
+}
@@ -0,0 +1,381 @@
+use super::cpu::{Cpu, State};
+use super::isa::Comparison;
+use std::{
+    error::Error,
+    fmt,
+    io::{BufRead, Write},
+    str::FromStr,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stop before this cycle runs
+    Cycle(usize),
+    /// Stop before the instruction at this address begins
+    Pc(usize),
+    /// Stop when X starts to satisfy the condition, having not satisfied it before
+    X(Comparison, i32),
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Cycle(c) => write!(f, "cycle {}", c),
+            Breakpoint::Pc(pc) => write!(f, "pc {}", pc),
+            Breakpoint::X(cmp, v) => write!(f, "x {} {}", cmp, v),
+        }
+    }
+}
+
+/// Why the debugger stopped running the program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// A single step finished
+    Stepped,
+    /// The breakpoint at this index was hit
+    Breakpoint(usize),
+    /// The program ran to the end
+    Complete,
+}
+
+/// Runs a Cpu a cycle at a time, stopping at breakpoints
+pub struct Debugger {
+    cpu: Cpu,
+    breakpoints: Vec<Breakpoint>,
+    /// The index of the breakpoint the last stop was at and the cycle it stopped before,
+    /// so running again only stops there for the breakpoints after it
+    stopped_at: Option<(usize, usize)>,
+}
+
+impl Debugger {
+    pub fn new(cpu: Cpu) -> Self {
+        Self {
+            cpu,
+            breakpoints: Vec::new(),
+            stopped_at: None,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    pub fn remove_breakpoint(&mut self, index: usize) -> Option<Breakpoint> {
+        // the indexes after this one move down
+        self.stopped_at = None;
+        (index < self.breakpoints.len()).then(|| self.breakpoints.remove(index))
+    }
+
+    /// Run up to count cycles, stopping early at a breakpoint or the end of the program
+    pub fn step(&mut self, count: usize) -> Stop {
+        let resumed_from = self.stopped_at.take();
+        for _ in 0..count {
+            if self.cpu.is_complete() {
+                return Stop::Complete;
+            }
+            let first = match resumed_from {
+                Some((index, cycle)) if cycle == self.cpu.current_cycle() => index + 1,
+                _ => 0,
+            };
+            if let Some(index) = self.breakpoint_before_cycle(first) {
+                self.stopped_at = Some((index, self.cpu.current_cycle()));
+                return Stop::Breakpoint(index);
+            }
+            let x_before = self.cpu.get_x();
+            self.cpu.cycle();
+            if let Some(index) = self.breakpoint_after_cycle(x_before) {
+                return Stop::Breakpoint(index);
+            }
+        }
+        if self.cpu.is_complete() {
+            Stop::Complete
+        } else {
+            Stop::Stepped
+        }
+    }
+
+    /// Run until a breakpoint is hit or the program finishes
+    pub fn resume(&mut self) -> Stop {
+        self.step(usize::MAX)
+    }
+
+    /// The first cycle or pc breakpoint from index first on which stops the next cycle
+    /// running
+    fn breakpoint_before_cycle(&self, first: usize) -> Option<usize> {
+        let cpu = &self.cpu;
+        self.breakpoints
+            .iter()
+            .enumerate()
+            .skip(first)
+            .find(|(_, b)| match **b {
+                Breakpoint::Cycle(c) => cpu.current_cycle() == c,
+                Breakpoint::Pc(pc) => cpu.pc() == pc && cpu.state() == State::BeginNextInstruction,
+                Breakpoint::X(..) => false,
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// The first x breakpoint whose condition the cycle just run made true
+    fn breakpoint_after_cycle(&self, x_before: i32) -> Option<usize> {
+        let x = self.cpu.get_x();
+        self.breakpoints.iter().position(|b| match *b {
+            Breakpoint::X(cmp, v) => cmp.holds(x, v) && !cmp.holds(x_before, v),
+            Breakpoint::Cycle(_) | Breakpoint::Pc(_) => false,
+        })
+    }
+
+    /// The registers and state of the Cpu, on one line
+    pub fn status(&self) -> String {
+        let registers = self
+            .cpu
+            .isa()
+            .registers()
+            .iter()
+            .zip(self.cpu.registers())
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let instruction = self
+            .cpu
+            .disassemble_current()
+            .unwrap_or_else(|| "-".to_owned());
+        let status = format!(
+            "pc={} cycle={} {} state={:?} instruction={}",
+            self.cpu.pc(),
+            self.cpu.current_cycle(),
+            registers,
+            self.cpu.state(),
+            instruction
+        );
+        match self.cpu.source(self.cpu.pc()) {
+            Some(entry) => format!("{} at {}", status, entry),
+            None => status,
+        }
+    }
+
+    /// The breakpoint, along with the source it stops at for a pc breakpoint
+    fn describe(&self, breakpoint: Breakpoint) -> String {
+        match breakpoint {
+            Breakpoint::Pc(pc) => match self.cpu.source(pc) {
+                Some(entry) => format!("{} ({})", breakpoint, entry),
+                None => breakpoint.to_string(),
+            },
+            _ => breakpoint.to_string(),
+        }
+    }
+
+    /// Read commands from input until the user quits or input runs out, writing
+    /// what happens to output
+    pub fn run_interactive(
+        &mut self,
+        input: impl BufRead,
+        mut output: impl Write,
+    ) -> Result<(), Box<dyn Error>> {
+        writeln!(output, "{}", self.status())?;
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let command = match line.parse::<Command>() {
+                Ok(command) => command,
+                Err(e) => {
+                    writeln!(output, "{}", e)?;
+                    continue;
+                }
+            };
+            match command {
+                Command::Step(count) => {
+                    let stop = self.step(count);
+                    self.report_stop(stop, &mut output)?
+                }
+                Command::Continue => {
+                    let stop = self.resume();
+                    self.report_stop(stop, &mut output)?
+                }
+                Command::Break(b) => {
+                    let index = self.add_breakpoint(b);
+                    writeln!(output, "Breakpoint {} at {}", index, self.describe(b))?;
+                }
+                Command::Delete(index) => match self.remove_breakpoint(index) {
+                    Some(b) => writeln!(output, "Deleted breakpoint {} at {}", index, b)?,
+                    None => writeln!(output, "No breakpoint {}", index)?,
+                },
+                Command::List => {
+                    for (i, b) in self.breakpoints.iter().enumerate() {
+                        writeln!(output, "{}: {}", i, self.describe(*b))?;
+                    }
+                }
+                Command::Print => writeln!(output, "{}", self.status())?,
+                Command::Help => writeln!(output, "{}", HELP)?,
+                Command::Quit => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn report_stop(&self, stop: Stop, output: &mut impl Write) -> std::io::Result<()> {
+        match stop {
+            Stop::Stepped => {}
+            Stop::Breakpoint(i) => {
+                let b = self.describe(self.breakpoints[i]);
+                writeln!(output, "Hit breakpoint {} at {}", i, b)?
+            }
+            Stop::Complete => writeln!(output, "Program complete")?,
+        }
+        writeln!(output, "{}", self.status())
+    }
+}
+
+const HELP: &str = "Commands:
+  s, step [n]           run n cycles (default 1)
+  c, continue           run until a breakpoint or the end
+  b, break cycle <n>    stop before cycle n
+  b, break pc <n>       stop before the instruction at n begins
+  b, break x <op> <n>   stop when x becomes <, <=, ==, !=, >= or > n
+  d, delete <i>         remove breakpoint i
+  l, list               list breakpoints
+  p, print              show the registers
+  h, help               show this
+  q, quit               stop debugging";
+
+/// Something typed at the debugger prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Step(usize),
+    Continue,
+    Break(Breakpoint),
+    Delete(usize),
+    List,
+    Print,
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = s.split_whitespace().collect::<Vec<_>>();
+        let number = |w: &str| {
+            w.parse::<usize>()
+                .map_err(|_| format!("'{}' is not a number", w))
+        };
+        match words.as_slice() {
+            ["s" | "step"] => Ok(Command::Step(1)),
+            ["s" | "step", n] => Ok(Command::Step(number(n)?)),
+            ["c" | "continue"] => Ok(Command::Continue),
+            ["b" | "break", "cycle", n] => Ok(Command::Break(Breakpoint::Cycle(number(n)?))),
+            ["b" | "break", "pc", n] => Ok(Command::Break(Breakpoint::Pc(number(n)?))),
+            ["b" | "break", "x", cmp, v] => Ok(Command::Break(Breakpoint::X(
+                cmp.parse()?,
+                v.parse().map_err(|_| format!("'{}' is not a number", v))?,
+            ))),
+            ["d" | "delete", n] => Ok(Command::Delete(number(n)?)),
+            ["l" | "list"] => Ok(Command::List),
+            ["p" | "print"] => Ok(Command::Print),
+            ["h" | "help"] => Ok(Command::Help),
+            ["q" | "quit"] => Ok(Command::Quit),
+            _ => Err(format!("Unknown command '{}', try help", s.trim())),
+        }
+    }
+}
+
+#[cfg(test)]
+const PROGRAM: &str = "noop
+addx 3
+addx -5";
+
+#[test]
+fn test_breakpoints() {
+    let mut debugger = Debugger::new(Cpu::compile(PROGRAM).unwrap());
+    debugger.add_breakpoint(Breakpoint::Pc(2));
+    debugger.add_breakpoint(Breakpoint::X(Comparison::Less, 0));
+    assert_eq!(debugger.resume(), Stop::Breakpoint(0));
+    assert_eq!(
+        debugger.status(),
+        "pc=2 cycle=4 x=4 state=BeginNextInstruction instruction=addx -5 at line 3: addx -5"
+    );
+    assert_eq!(debugger.resume(), Stop::Breakpoint(1));
+    assert_eq!(debugger.cpu().get_x(), -1);
+    assert_eq!(debugger.resume(), Stop::Complete);
+}
+
+#[test]
+fn test_step_and_cycle_breakpoint() {
+    let mut debugger = Debugger::new(Cpu::compile(PROGRAM).unwrap());
+    assert_eq!(debugger.step(2), Stop::Stepped);
+    assert_eq!(debugger.cpu().current_cycle(), 3);
+    debugger.add_breakpoint(Breakpoint::Cycle(5));
+    assert_eq!(debugger.step(10), Stop::Breakpoint(0));
+    assert_eq!(debugger.cpu().current_cycle(), 5);
+}
+
+#[test]
+fn test_breakpoints_before_the_first_cycle() {
+    let mut debugger = Debugger::new(Cpu::compile(PROGRAM).unwrap());
+    debugger.add_breakpoint(Breakpoint::Cycle(1));
+    debugger.add_breakpoint(Breakpoint::Pc(0));
+    assert_eq!(debugger.resume(), Stop::Breakpoint(0));
+    assert_eq!(debugger.cpu().current_cycle(), 1);
+    // both breakpoints are at the same place, so the other one stops it next
+    assert_eq!(debugger.resume(), Stop::Breakpoint(1));
+    assert_eq!(debugger.cpu().current_cycle(), 1);
+    assert_eq!(debugger.step(1), Stop::Stepped);
+    assert_eq!(debugger.cpu().current_cycle(), 2);
+    assert_eq!(debugger.resume(), Stop::Complete);
+}
+
+#[test]
+fn test_interactive_session() {
+    let mut debugger = Debugger::new(Cpu::compile(PROGRAM).unwrap());
+    let commands = "break x == 4\ncontinue\nbogus\nstep\nquit\nstep\n";
+    let mut output = Vec::new();
+    debugger
+        .run_interactive(commands.as_bytes(), &mut output)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(
+        output.lines().collect::<Vec<_>>(),
+        vec![
+            "pc=0 cycle=1 x=1 state=BeginNextInstruction instruction=noop at line 1: noop",
+            "Breakpoint 0 at x == 4",
+            "Hit breakpoint 0 at x == 4",
+            "pc=2 cycle=4 x=4 state=BeginNextInstruction instruction=addx -5 at line 3: addx -5",
+            "Unknown command 'bogus', try help",
+            "pc=2 cycle=5 x=4 state=MidInstruction(1) instruction=addx -5 at line 3: addx -5",
+        ]
+    );
+}
+
+#[test]
+fn test_shows_source_and_labels() {
+    let source = "start:
+  noop
+  addx 3 ; bump";
+    let mut debugger = Debugger::new(Cpu::compile(source).unwrap());
+    let commands = "break pc 1\nbreak pc 0\nlist\ncontinue\n";
+    let mut output = Vec::new();
+    debugger
+        .run_interactive(commands.as_bytes(), &mut output)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(
+        output.lines().collect::<Vec<_>>(),
+        vec![
+            "pc=0 cycle=1 x=1 state=BeginNextInstruction instruction=noop at line 2: start: noop",
+            "Breakpoint 0 at pc 1 (line 3: addx 3)",
+            "Breakpoint 1 at pc 0 (line 2: start: noop)",
+            "0: pc 1 (line 3: addx 3)",
+            "1: pc 0 (line 2: start: noop)",
+            "Hit breakpoint 1 at pc 0 (line 2: start: noop)",
+            "pc=0 cycle=1 x=1 state=BeginNextInstruction instruction=noop at line 2: start: noop",
+        ]
+    );
+}
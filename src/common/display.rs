@@ -1,7 +1,8 @@
 use super::grid::{self, Grid};
-use std::fmt;
+use std::{fmt, str::FromStr};
 
 /// A simple wrapper around a Grid<bool> specialised to be a monochrome display screen
+#[derive(Clone)]
 pub struct Display {
     grid: Grid<bool>,
 }
@@ -16,6 +17,10 @@ impl Display {
     pub fn set(&mut self, x: usize, y: usize, pixel: bool) -> Result<(), grid::GridOperationError> {
         self.grid.set(x, y, pixel)
     }
+
+    pub fn grid(&self) -> &Grid<bool> {
+        &self.grid
+    }
 }
 
 impl fmt::Display for Display {
@@ -38,6 +43,36 @@ impl fmt::Display for Display {
     }
 }
 
+impl FromStr for Display {
+    type Err = String;
+
+    /// Parse the same `#` and `.` format that the display is printed in
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s.lines().map(|l| l.trim()).collect::<Vec<_>>();
+        let width = lines.first().map(|l| l.len()).unwrap_or(0);
+        let mut display = Display::new(width, lines.len());
+        for (y, line) in lines.into_iter().enumerate() {
+            if line.len() != width {
+                return Err(format!(
+                    "Display line {} is {} pixels wide, expected {}",
+                    y,
+                    line.len(),
+                    width
+                ));
+            }
+            for (x, c) in line.chars().enumerate() {
+                let pixel = match c {
+                    '#' => true,
+                    '.' => false,
+                    _ => return Err(format!("Unexpected pixel '{}' at {},{}", c, x, y)),
+                };
+                display.set(x, y, pixel).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(display)
+    }
+}
+
 #[test]
 fn test_display() {
     let mut display = Display::new(3, 2);
@@ -52,3 +87,13 @@ fn test_display() {
         .to_owned()
     );
 }
+
+#[test]
+fn test_display_round_trip() {
+    let text = "#..#
+.##.
+";
+    let display = Display::from_str(text).unwrap();
+    assert_eq!(display.to_string(), text);
+    assert!(Display::from_str("#.\n#").is_err());
+}
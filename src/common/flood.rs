@@ -0,0 +1,225 @@
+#[cfg(test)]
+use super::grid::Grid;
+use super::voxel::{BoundingBox, Neighbourhood3, Point3};
+use std::{
+    collections::{HashSet, VecDeque},
+    hash::Hash,
+};
+
+/// A position which can be flood filled
+pub trait Position: Copy + Eq + Hash + Ord {}
+
+impl<P: Copy + Eq + Hash + Ord> Position for P {}
+
+/// A single connected component found by a flood fill
+#[derive(Debug, Clone)]
+pub struct Region<P> {
+    cells: HashSet<P>,
+    #[cfg(test)]
+    boundary: Vec<P>,
+    touches_border: bool,
+}
+
+impl<P: Position> Region<P> {
+    #[cfg(test)]
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn contains(&self, p: &P) -> bool {
+        self.cells.contains(p)
+    }
+
+    /// All cells in the region, in sorted order
+    #[cfg(test)]
+    pub fn cells(&self) -> Vec<P> {
+        let mut cells = self.cells.iter().copied().collect::<Vec<_>>();
+        cells.sort();
+        cells
+    }
+
+    /// Cells in the region which have at least one neighbour outside it, in sorted order
+    #[cfg(test)]
+    pub fn boundary(&self) -> &[P] {
+        &self.boundary
+    }
+
+    #[cfg(test)]
+    pub fn touches_border(&self) -> bool {
+        self.touches_border
+    }
+
+    /// An enclosed region is one which does not reach the edge of the space being filled
+    pub fn is_enclosed(&self) -> bool {
+        !self.touches_border
+    }
+}
+
+/// Flood fill from start, returning every reachable position for which include is true.
+/// Neighbours must only return positions within the space being filled, and is_border
+/// tells us whether a position lies on the edge of that space.
+fn fill_from<P: Position>(
+    start: P,
+    neighbours: &impl Fn(P) -> Vec<P>,
+    include: &impl Fn(P) -> bool,
+    is_border: &impl Fn(P) -> bool,
+) -> Region<P> {
+    let mut cells = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(p) = queue.pop_front() {
+        for n in neighbours(p) {
+            if include(n) && cells.insert(n) {
+                queue.push_back(n);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    let boundary = {
+        let mut boundary = cells
+            .iter()
+            .copied()
+            .filter(|p| is_border(*p) || neighbours(*p).into_iter().any(|n| !cells.contains(&n)))
+            .collect::<Vec<_>>();
+        boundary.sort();
+        boundary
+    };
+    let touches_border = cells.iter().any(|p| is_border(*p));
+
+    Region {
+        cells,
+        #[cfg(test)]
+        boundary,
+        touches_border,
+    }
+}
+
+/// Label every connected component of positions for which include is true
+fn label_components<P: Position>(
+    positions: impl Iterator<Item = P>,
+    neighbours: impl Fn(P) -> Vec<P>,
+    include: impl Fn(P) -> bool,
+    is_border: impl Fn(P) -> bool,
+) -> Vec<Region<P>> {
+    let mut seen = HashSet::new();
+    let mut regions = Vec::new();
+    for p in positions {
+        if seen.contains(&p) || !include(p) {
+            continue;
+        }
+        let region = fill_from(p, &neighbours, &include, &is_border);
+        seen.extend(region.cells.iter().copied());
+        regions.push(region);
+    }
+    regions
+}
+
+/// Find all orthogonally connected regions of the grid whose cells satisfy the predicate.
+/// Regions are returned in the order their first cell appears in `iter_coords`.
+#[cfg(test)]
+pub fn label_grid<T>(
+    grid: &Grid<T>,
+    predicate: impl Fn(&T) -> bool,
+) -> Vec<Region<(usize, usize)>> {
+    label_components(
+        grid.iter_coords(),
+        |(x, y)| grid.surrounding(x, y).unwrap_or_default(),
+        |(x, y)| grid.get(x, y).map(&predicate).unwrap_or(false),
+        |(x, y)| x == 0 || y == 0 || x + 1 == grid.width() || y + 1 == grid.height(),
+    )
+}
+
+impl Region<(usize, usize)> {
+    /// The smallest and largest corners of the box containing every cell in the region
+    #[cfg(test)]
+    pub fn bounding_box(&self) -> ((usize, usize), (usize, usize)) {
+        let xs = self.cells.iter().map(|(x, _)| *x);
+        let ys = self.cells.iter().map(|(_, y)| *y);
+        (
+            (xs.clone().min().unwrap(), ys.clone().min().unwrap()),
+            (xs.max().unwrap(), ys.max().unwrap()),
+        )
+    }
+}
+
+/// Find all face-connected regions of voxels within the bounding box which satisfy the predicate
+pub fn label_voxels(
+    bounds: BoundingBox,
+    predicate: impl Fn(&Point3) -> bool,
+) -> Vec<Region<Point3>> {
+    label_components(
+        bounds.points(),
+        |p| {
+            p.neighbours(Neighbourhood3::Faces)
+                .filter(|n| bounds.contains(n))
+                .collect()
+        },
+        |p| predicate(&p),
+        |p| bounds.on_surface(&p),
+    )
+}
+
+/// The regions which don't touch the edge of the space they were found in
+pub fn enclosed_regions<P: Position>(regions: &[Region<P>]) -> impl Iterator<Item = &Region<P>> {
+    regions.iter().filter(|r| r.is_enclosed())
+}
+
+#[cfg(test)]
+fn grid_from_str(s: &str) -> Grid<bool> {
+    let lines = s.lines().collect::<Vec<_>>();
+    let mut grid = Grid::new(lines[0].len(), lines.len());
+    for (y, line) in lines.into_iter().enumerate() {
+        for (x, c) in line.chars().enumerate() {
+            grid.set(x, y, c == '#').unwrap();
+        }
+    }
+    grid
+}
+
+#[test]
+fn test_label_grid() {
+    let grid = grid_from_str(
+        "##...
+#..#.
+...##
+.....",
+    );
+    let regions = label_grid(&grid, |c| *c);
+    assert_eq!(regions.len(), 2);
+    assert_eq!(regions[0].cells(), vec![(0, 0), (0, 1), (1, 0)]);
+    assert_eq!(regions[0].bounding_box(), ((0, 0), (1, 1)));
+    assert_eq!(regions[1].size(), 3);
+    assert_eq!(regions[1].bounding_box(), ((3, 1), (4, 2)));
+    assert!(regions.iter().all(|r| r.touches_border()));
+}
+
+#[test]
+fn test_enclosed_grid_region() {
+    let grid = grid_from_str(
+        "#####
+#...#
+#.#.#
+#...#
+#####",
+    );
+    let regions = label_grid(&grid, |c| !*c);
+    assert_eq!(regions.len(), 1);
+    let enclosed = enclosed_regions(&regions).collect::<Vec<_>>();
+    assert_eq!(enclosed.len(), 1);
+    assert_eq!(enclosed[0].size(), 8);
+    assert_eq!(enclosed[0].boundary().len(), 8);
+    assert!(!enclosed[0].contains(&(2, 2)));
+}
+
+#[test]
+fn test_label_voxels() {
+    // a hollow 3x3x3 cube has a single enclosed cell of air in the middle
+    let centre = Point3::new(1, 1, 1);
+    let solid = BoundingBox::new(Point3::new(0, 0, 0), Point3::new(2, 2, 2));
+    let air = label_voxels(solid.expand(1), |p| !solid.contains(p) || *p == centre);
+    assert_eq!(air.len(), 2);
+    assert!(air[0].touches_border());
+    assert_eq!(air[0].size(), 125 - 27);
+    assert_eq!(air[1].cells(), vec![centre]);
+    assert!(air[1].is_enclosed());
+}
@@ -1,5 +1,16 @@
-use std::{error::Error, fmt::Display, iter};
-
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fmt::Display,
+    iter,
+    ops::{Index, IndexMut},
+};
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(
+    try_from = "super::serialise::GridData<T>",
+    bound(deserialize = "T: Deserialize<'de>")
+)]
 pub struct Grid<T> {
     content: Vec<T>,
     width: usize,
@@ -9,6 +20,12 @@ pub struct Grid<T> {
 #[derive(Debug)]
 pub enum GridOperationError {
     IndexOutOfBounds(usize, usize, usize, usize),
+    /// Two grids should have been the same size, but were width x height and other width x height
+    SizeMismatch(usize, usize, usize, usize),
+    /// The coordinates are outside a grid which isn't allowed to grow to fit them
+    CannotGrow(i64, i64),
+    /// Only the four directions along the axes are supported, but got this dx, dy
+    NotCardinal(isize, isize),
 }
 
 impl Error for GridOperationError {
@@ -25,6 +42,19 @@ impl Display for GridOperationError {
                 "The given coordinates {},{} are not inside the {}x{} grid",
                 x, y, width, height
             )?,
+            GridOperationError::SizeMismatch(width, height, other_width, other_height) => write!(
+                f,
+                "A {}x{} grid can't be combined with a {}x{} grid",
+                width, height, other_width, other_height
+            )?,
+            GridOperationError::CannotGrow(x, y) => {
+                write!(f, "The grid is not allowed to grow to include {},{}", x, y)?
+            }
+            GridOperationError::NotCardinal(dx, dy) => write!(
+                f,
+                "The direction {},{} is not along one of the grid's axes",
+                dx, dy
+            )?,
         }
         Ok(())
     }
@@ -36,14 +66,51 @@ where
 {
     pub fn new(width: usize, height: usize) -> Self {
         Self {
-            content: iter::repeat(T::default()).take(width * height).collect(),
+            content: iter::repeat_n(T::default(), width * height).collect(),
             width,
             height,
         }
     }
+
+    /// Add default cells on each side of the grid. Anything added on the left or top
+    /// moves the existing cells, so (x, y) becomes (x + left, y + top).
+    pub fn grow(&mut self, left: usize, top: usize, right: usize, bottom: usize) {
+        let width = left + self.width + right;
+        let height = top + self.height + bottom;
+        let mut content = Vec::with_capacity(width * height);
+        content.extend(iter::repeat_n(T::default(), top * width));
+        for y in 0..self.height {
+            let row = &self.content[y * self.width..(y + 1) * self.width];
+            content.extend(iter::repeat_n(T::default(), left));
+            content.extend(row.iter().cloned());
+            content.extend(iter::repeat_n(T::default(), right));
+        }
+        content.extend(iter::repeat_n(T::default(), bottom * width));
+        self.content = content;
+        self.width = width;
+        self.height = height;
+    }
 }
 
 impl<T> Grid<T> {
+    /// A grid made from cells listed a row at a time, top to bottom
+    pub fn from_content(content: Vec<T>, width: usize, height: usize) -> Result<Self, String> {
+        if content.len() != width * height {
+            return Err(format!(
+                "A {}x{} grid needs {} cells but there are {}",
+                width,
+                height,
+                width * height,
+                content.len()
+            ));
+        }
+        Ok(Self {
+            content,
+            width,
+            height,
+        })
+    }
+
     pub fn set(&mut self, x: usize, y: usize, value: T) -> Result<(), GridOperationError> {
         let index = self.index_of(x, y)?;
         self.content[index] = value;
@@ -62,59 +129,123 @@ impl<T> Grid<T> {
             ))
     }
 
+    #[cfg(test)]
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Result<&mut T, GridOperationError> {
+        let index = self.index_of(x, y)?;
+        Ok(&mut self.content[index])
+    }
+
+    /// Every cell along with its coordinates, in the same order as iter_coords
+    pub fn enumerate(&self) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+        self.iter_coords().zip(self.content.iter())
+    }
+
+    /// A new grid of the same size with f applied to every cell
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid {
+            content: self.content.iter().map(f).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Combine two grids of the same size cell by cell
+    pub fn zip_with<U, V>(
+        &self,
+        other: &Grid<U>,
+        f: impl Fn(&T, &U) -> V,
+    ) -> Result<Grid<V>, GridOperationError> {
+        if self.width != other.width || self.height != other.height {
+            return Err(GridOperationError::SizeMismatch(
+                self.width,
+                self.height,
+                other.width,
+                other.height,
+            ));
+        }
+        Ok(Grid {
+            content: self
+                .content
+                .iter()
+                .zip(other.content.iter())
+                .map(|(t, u)| f(t, u))
+                .collect(),
+            width: self.width,
+            height: self.height,
+        })
+    }
+
+    /// Fold each row from left to right, giving one result per row
+    #[cfg(test)]
+    pub fn fold_rows<A: Clone>(&self, init: A, f: impl Fn(A, &T) -> A) -> Vec<A> {
+        (0..self.height)
+            .map(|y| (0..self.width).fold(init.clone(), |acc, x| f(acc, &self[(x, y)])))
+            .collect()
+    }
+
+    /// Fold each column from top to bottom, giving one result per column
+    #[cfg(test)]
+    pub fn fold_cols<A: Clone>(&self, init: A, f: impl Fn(A, &T) -> A) -> Vec<A> {
+        (0..self.width)
+            .map(|x| (0..self.height).fold(init.clone(), |acc, y| f(acc, &self[(x, y)])))
+            .collect()
+    }
+
+    /// The number of cells satisfying the predicate
+    pub fn count(&self, predicate: impl Fn(&T) -> bool) -> usize {
+        self.content.iter().filter(|t| predicate(t)).count()
+    }
+
+    /// The first cell in iter_coords order satisfying the predicate, with its coordinates
+    #[cfg(test)]
+    pub fn find(&self, predicate: impl Fn(&T) -> bool) -> Option<((usize, usize), &T)> {
+        self.enumerate().find(|(_, t)| predicate(t))
+    }
+
+    /// The coordinates of the first cell in iter_coords order satisfying the predicate
+    #[cfg(test)]
+    pub fn position(&self, predicate: impl Fn(&T) -> bool) -> Option<(usize, usize)> {
+        self.find(predicate).map(|(p, _)| p)
+    }
+
+    #[cfg(test)]
     pub fn all_left_of(
         &self,
         x: usize,
         y: usize,
         predicate: impl Fn(&T) -> bool,
     ) -> Result<bool, GridOperationError> {
-        if self.out_of_bounds(x, y) {
-            Err(self.index_out_of_bounds(x, y))
-        } else {
-            Ok(self.iter_left_of(x, y).all(predicate))
-        }
+        Ok(self.ray(x, y, (-1, 0))?.all(|(_, t)| predicate(t)))
     }
 
+    #[cfg(test)]
     pub fn all_right_of(
         &self,
         x: usize,
         y: usize,
         predicate: impl Fn(&T) -> bool,
     ) -> Result<bool, GridOperationError> {
-        if self.out_of_bounds(x, y) {
-            Err(self.index_out_of_bounds(x, y))
-        } else {
-            Ok(self.iter_right_of(x, y).all(predicate))
-        }
+        Ok(self.ray(x, y, (1, 0))?.all(|(_, t)| predicate(t)))
     }
 
+    #[cfg(test)]
     pub fn all_above(
         &self,
         x: usize,
         y: usize,
         predicate: impl Fn(&T) -> bool,
     ) -> Result<bool, GridOperationError> {
-        if self.out_of_bounds(x, y) {
-            Err(self.index_out_of_bounds(x, y))
-        } else {
-            Ok(self.iter_above(x, y).all(predicate))
-        }
+        Ok(self.ray(x, y, (0, -1))?.all(|(_, t)| predicate(t)))
     }
 
+    #[cfg(test)]
     pub fn all_below(
         &self,
         x: usize,
         y: usize,
         predicate: impl Fn(&T) -> bool,
     ) -> Result<bool, GridOperationError> {
-        if self.out_of_bounds(x, y) {
-            Err(self.index_out_of_bounds(x, y))
-        } else {
-            Ok(self.iter_below(x, y).all(predicate))
-        }
-    }
-    fn index_out_of_bounds(&self, x: usize, y: usize) -> GridOperationError {
-        GridOperationError::IndexOutOfBounds(x, y, self.width, self.height)
+        Ok(self.ray(x, y, (0, 1))?.all(|(_, t)| predicate(t)))
     }
 
     fn out_of_bounds(&self, x: usize, y: usize) -> bool {
@@ -125,53 +256,9 @@ impl<T> Grid<T> {
         (0..self.height).flat_map(move |y| (0..self.width).map(move |x| (x, y)))
     }
 
-    pub fn iter_coords_left_of(
-        &self,
-        x: usize,
-        y: usize,
-    ) -> impl Iterator<Item = (usize, usize)> + '_ {
-        (0..x).rev().map(move |x| (x, y))
-    }
-
-    pub fn iter_coords_right_of(
-        &self,
-        x: usize,
-        y: usize,
-    ) -> impl Iterator<Item = (usize, usize)> + '_ {
-        (x..=self.max_x()).skip(1).map(move |x| (x, y))
-    }
-
-    pub fn iter_coords_above(
-        &self,
-        x: usize,
-        y: usize,
-    ) -> impl Iterator<Item = (usize, usize)> + '_ {
-        (0..y).rev().map(move |y| (x, y))
-    }
-
-    pub fn iter_coords_below(
-        &self,
-        x: usize,
-        y: usize,
-    ) -> impl Iterator<Item = (usize, usize)> + '_ {
-        (y..=self.max_y()).skip(1).map(move |y| (x, y))
-    }
-
-    pub fn iter_left_of(&self, x: usize, y: usize) -> impl Iterator<Item = &T> {
-        self.iter_coords_left_of(x, y)
-            .map(|(x, y)| self.get(x, y).unwrap())
-    }
-    pub fn iter_right_of(&self, x: usize, y: usize) -> impl Iterator<Item = &T> {
-        self.iter_coords_right_of(x, y)
-            .map(|(x, y)| self.get(x, y).unwrap())
-    }
-    pub fn iter_above(&self, x: usize, y: usize) -> impl Iterator<Item = &T> {
-        self.iter_coords_above(x, y)
-            .map(|(x, y)| self.get(x, y).unwrap())
-    }
-    pub fn iter_below(&self, x: usize, y: usize) -> impl Iterator<Item = &T> {
-        self.iter_coords_below(x, y)
-            .map(|(x, y)| self.get(x, y).unwrap())
+    /// Mutable access to each row of the grid in turn, from y = 0
+    pub fn rows_mut(&mut self) -> std::slice::ChunksMut<'_, T> {
+        self.content.chunks_mut(self.width.max(1))
     }
 
     pub fn width(&self) -> usize {
@@ -237,6 +324,27 @@ impl<T> Grid<T> {
     }
 }
 
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    /// Panics if the coordinates are outside the grid; use get for a Result instead
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        match self.get(x, y) {
+            Ok(t) => t,
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
+        match self.index_of(x, y) {
+            Ok(index) => &mut self.content[index],
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
 #[test]
 fn test_all_left_of() {
     let mut grid = Grid::new(3, 3);
@@ -275,6 +383,25 @@ fn test_all_below() {
     assert!(grid.all_below(1, 0, |t| *t >= 1).unwrap());
 }
 
+#[test]
+fn test_grow() {
+    let mut grid: Grid<u8> = Grid::new(2, 1);
+    grid[(0, 0)] = 1;
+    grid[(1, 0)] = 2;
+    grid.grow(1, 2, 0, 1);
+    assert_eq!((grid.width(), grid.height()), (3, 4));
+    assert_eq!(grid[(1, 2)], 1);
+    assert_eq!(grid[(2, 2)], 2);
+    assert_eq!(grid.count(|t| *t == 0), 10);
+
+    // rows with no cells still get their padding
+    let mut empty: Grid<u8> = Grid::new(0, 3);
+    empty.grow(1, 0, 1, 0);
+    assert_eq!((empty.width(), empty.height()), (2, 3));
+    assert_eq!(empty.count(|t| *t == 0), 6);
+    assert_eq!(empty[(1, 2)], 0);
+}
+
 #[test]
 fn test_iter_coords() {
     let grid: Grid<bool> = Grid::new(2, 3);
@@ -283,3 +410,39 @@ fn test_iter_coords() {
         vec![(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)]
     );
 }
+
+#[test]
+fn test_index_and_get_mut() {
+    let mut grid: Grid<u8> = Grid::new(2, 2);
+    grid[(1, 0)] = 3;
+    *grid.get_mut(0, 1).unwrap() += 2;
+    assert_eq!(grid[(1, 0)], 3);
+    assert_eq!(*grid.get(0, 1).unwrap(), 2);
+    assert!(grid.get_mut(2, 0).is_err());
+}
+
+#[test]
+#[should_panic]
+fn test_index_out_of_bounds() {
+    let grid: Grid<u8> = Grid::new(2, 2);
+    let _ = grid[(0, 2)];
+}
+
+#[test]
+fn test_combinators() {
+    let mut grid: Grid<u32> = Grid::new(3, 2);
+    for (x, y) in grid.iter_coords().collect::<Vec<_>>() {
+        grid[(x, y)] = (y * 3 + x) as u32;
+    }
+    assert_eq!(grid.fold_rows(0, |a, t| a + t), vec![3, 12]);
+    assert_eq!(grid.fold_cols(0, |a, t| a + t), vec![3, 5, 7]);
+    assert_eq!(grid.count(|t| t % 2 == 0), 3);
+    assert_eq!(grid.find(|t| *t > 3), Some(((1, 1), &4)));
+    assert_eq!(grid.position(|t| *t > 10), None);
+
+    let doubled = grid.map(|t| t * 2);
+    assert_eq!(doubled[(2, 1)], 10);
+    let summed = grid.zip_with(&doubled, |a, b| a + b).unwrap();
+    assert_eq!(summed[(2, 1)], 15);
+    assert!(grid.zip_with(&Grid::<u8>::new(2, 3), |_, _| 0).is_err());
+}
@@ -0,0 +1,194 @@
+use super::grid::{Grid, GridOperationError};
+use serde::{Deserialize, Serialize};
+
+/// What a GrowableGrid does when a cell outside it is set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Growth {
+    /// Refuse, returning an error
+    Fixed,
+    /// Grow just enough to include the new cell
+    Exact,
+    /// Grow by at least this many cells on whichever side is too small, so a run of
+    /// sets creeping outwards doesn't copy the whole grid every time
+    #[cfg(test)]
+    Chunked(usize),
+}
+
+/// A Grid addressed by signed coordinates which can grow in any direction. The
+/// origin is the outside coordinate of the inner grid's (0, 0), so cells keep their
+/// coordinates however many rows and columns get added before them. Cells that
+/// haven't been added yet read as the default value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de> + Default"))]
+pub struct GrowableGrid<T> {
+    grid: Grid<T>,
+    origin: (i64, i64),
+    growth: Growth,
+    #[serde(skip)]
+    outside: T,
+}
+
+impl<T> GrowableGrid<T>
+where
+    T: Default + Clone,
+{
+    /// An empty grid, which will be placed wherever the first cell is set
+    pub fn new(growth: Growth) -> Self {
+        Self {
+            grid: Grid::new(0, 0),
+            origin: (0, 0),
+            growth,
+            outside: T::default(),
+        }
+    }
+
+    /// A grid already covering min to max inclusive, or None if min is past max on
+    /// either axis
+    pub fn with_bounds(min: (i64, i64), max: (i64, i64), growth: Growth) -> Option<Self> {
+        if min.0 > max.0 || min.1 > max.1 {
+            return None;
+        }
+        let width = usize::try_from(max.0.checked_sub(min.0)?.checked_add(1)?).ok()?;
+        let height = usize::try_from(max.1.checked_sub(min.1)?.checked_add(1)?).ok()?;
+        Some(Self {
+            grid: Grid::new(width, height),
+            origin: min,
+            growth,
+            outside: T::default(),
+        })
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> &T {
+        match self.to_inner(x, y) {
+            Some((ix, iy)) => &self.grid[(ix, iy)],
+            None => &self.outside,
+        }
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, value: T) -> Result<(), GridOperationError> {
+        self.grow_to_include(x, y)?;
+        let (ix, iy) = self
+            .to_inner(x, y)
+            .expect("grid has just grown to include the coordinates");
+        self.grid.set(ix, iy, value)
+    }
+
+    /// Make sure (x, y) is inside the grid, growing it according to the policy if not
+    pub fn grow_to_include(&mut self, x: i64, y: i64) -> Result<(), GridOperationError> {
+        if self.contains(x, y) {
+            return Ok(());
+        }
+        let extra = match self.growth {
+            Growth::Fixed => return Err(GridOperationError::CannotGrow(x, y)),
+            Growth::Exact => 0,
+            #[cfg(test)]
+            Growth::Chunked(n) => n.saturating_sub(1),
+        };
+        if self.is_empty() {
+            // start with a whole chunk, with the cell in the middle of it
+            self.grid = Grid::new(extra + 1, extra + 1);
+            let before = (extra / 2) as i64;
+            self.origin = (x - before, y - before);
+            return Ok(());
+        }
+
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds().expect("grid is not empty");
+        let pad = |short: i64| if short > 0 { short as usize + extra } else { 0 };
+        let (left, top) = (pad(min_x - x), pad(min_y - y));
+        let (right, bottom) = (pad(x - max_x), pad(y - max_y));
+        self.grid.grow(left, top, right, bottom);
+        self.origin = (self.origin.0 - left as i64, self.origin.1 - top as i64);
+        Ok(())
+    }
+
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        self.to_inner(x, y).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.grid.width() == 0 || self.grid.height() == 0
+    }
+
+    /// The smallest and largest coordinates inside the grid, or None if it's empty
+    pub fn bounds(&self) -> Option<((i64, i64), (i64, i64))> {
+        if self.is_empty() {
+            None
+        } else {
+            Some((
+                self.origin,
+                (
+                    self.origin.0 + self.grid.width() as i64 - 1,
+                    self.origin.1 + self.grid.height() as i64 - 1,
+                ),
+            ))
+        }
+    }
+
+    /// The outside coordinates of the inner grid's (0, 0)
+    #[cfg(test)]
+    pub fn origin(&self) -> (i64, i64) {
+        self.origin
+    }
+
+    #[cfg(test)]
+    pub fn grid(&self) -> &Grid<T> {
+        &self.grid
+    }
+
+    pub fn into_grid(self) -> Grid<T> {
+        self.grid
+    }
+
+    fn to_inner(&self, x: i64, y: i64) -> Option<(usize, usize)> {
+        let ix = usize::try_from(x - self.origin.0).ok()?;
+        let iy = usize::try_from(y - self.origin.1).ok()?;
+        (ix < self.grid.width() && iy < self.grid.height()).then_some((ix, iy))
+    }
+}
+
+#[test]
+fn test_growing_keeps_coordinates() {
+    let mut grid = GrowableGrid::new(Growth::Exact);
+    assert_eq!(grid.bounds(), None);
+    grid.set(5, 5, 'a').unwrap();
+    assert_eq!(grid.bounds(), Some(((5, 5), (5, 5))));
+    grid.set(-2, 7, 'b').unwrap();
+    grid.set(6, 3, 'c').unwrap();
+    assert_eq!(grid.bounds(), Some(((-2, 3), (6, 7))));
+    assert_eq!(grid.origin(), (-2, 3));
+    assert_eq!(*grid.get(5, 5), 'a');
+    assert_eq!(*grid.get(-2, 7), 'b');
+    assert_eq!(*grid.get(6, 3), 'c');
+    assert_eq!(*grid.get(100, -100), char::default());
+    assert_eq!(grid.grid()[(7, 2)], 'a');
+}
+
+#[test]
+fn test_growth_policies() {
+    let mut fixed = GrowableGrid::with_bounds((0, 0), (2, 2), Growth::Fixed).unwrap();
+    fixed.set(2, 2, true).unwrap();
+    assert!(fixed.set(3, 0, true).is_err());
+
+    let mut chunked = GrowableGrid::with_bounds((0, 0), (2, 2), Growth::Chunked(4)).unwrap();
+    chunked.set(-1, 1, true).unwrap();
+    assert_eq!(chunked.bounds(), Some(((-4, 0), (2, 2))));
+    // already inside, so no more growth
+    chunked.set(-3, 1, true).unwrap();
+    assert_eq!(chunked.bounds(), Some(((-4, 0), (2, 2))));
+}
+
+#[test]
+fn test_bounds_must_be_in_order() {
+    assert!(GrowableGrid::<bool>::with_bounds((3, 0), (2, 2), Growth::Exact).is_none());
+    assert!(GrowableGrid::<bool>::with_bounds((0, 3), (2, 2), Growth::Exact).is_none());
+    let single = GrowableGrid::<bool>::with_bounds((2, 2), (2, 2), Growth::Exact).unwrap();
+    assert_eq!(single.bounds(), Some(((2, 2), (2, 2))));
+}
+
+#[test]
+fn test_first_set_grows_a_chunk() {
+    let mut grid = GrowableGrid::new(Growth::Chunked(5));
+    grid.set(10, -10, 'a').unwrap();
+    assert_eq!(grid.bounds(), Some(((8, -12), (12, -8))));
+    assert_eq!(*grid.get(10, -10), 'a');
+}
@@ -0,0 +1,228 @@
+use super::display::Display;
+use super::grid::Grid;
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Colour {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Colour {
+    pub const BLACK: Colour = Colour::new(0, 0, 0);
+    pub const WHITE: Colour = Colour::new(255, 255, 255);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    pub const fn grey(v: u8) -> Self {
+        Self::new(v, v, v)
+    }
+
+    /// As written in CSS and SVG, like #ff8000
+    pub fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// Perceived brightness, used when writing greyscale and monochrome images
+    pub fn luma(&self) -> u8 {
+        ((self.r as u32 * 299 + self.g as u32 * 587 + self.b as u32 * 114) / 1000) as u8
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Netpbm monochrome bitmap, dark colours are set pixels
+    Pbm,
+    /// Netpbm greyscale
+    Pgm,
+    /// Netpbm full colour
+    Ppm,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "pbm" => Some(ImageFormat::Pbm),
+            "pgm" => Some(ImageFormat::Pgm),
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+}
+
+/// Where images should be written, if the user asked for them by setting AOC_IMAGE_DIR
+pub fn output_path(file_name: &str) -> Option<PathBuf> {
+    std::env::var_os("AOC_IMAGE_DIR").map(|dir| PathBuf::from(dir).join(file_name))
+}
+
+/// Expand the grid into rows of pixels, with each cell becoming a scale x scale square
+fn pixels<T>(grid: &Grid<T>, scale: usize, colour: impl Fn(&T) -> Colour) -> Vec<Vec<Colour>> {
+    let mut rows = Vec::with_capacity(grid.height() * scale);
+    for y in 0..grid.height() {
+        let row = (0..grid.width())
+            .flat_map(|x| {
+                let c = colour(grid.get(x, y).expect("coordinates are within the grid"));
+                std::iter::repeat_n(c, scale)
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..scale {
+            rows.push(row.clone());
+        }
+    }
+    rows
+}
+
+/// Encode the grid as an image in the given format
+pub fn encode<T>(
+    grid: &Grid<T>,
+    format: ImageFormat,
+    scale: usize,
+    colour: impl Fn(&T) -> Colour,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    if scale == 0 {
+        return Err("Image scale must be at least 1".into());
+    }
+    let rows = pixels(grid, scale, colour);
+    let width = grid.width() * scale;
+    let height = grid.height() * scale;
+
+    let mut out = Vec::new();
+    match format {
+        ImageFormat::Pbm => {
+            out.extend(format!("P4\n{} {}\n", width, height).bytes());
+            for row in rows {
+                // each row is packed into bytes, most significant bit first, padded at the end
+                for chunk in row.chunks(8) {
+                    let byte = chunk
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, c)| c.luma() < 128)
+                        .fold(0u8, |b, (i, _)| b | (0x80 >> i));
+                    out.push(byte);
+                }
+            }
+        }
+        ImageFormat::Pgm => {
+            out.extend(format!("P5\n{} {}\n255\n", width, height).bytes());
+            out.extend(rows.iter().flatten().map(|c| c.luma()));
+        }
+        ImageFormat::Ppm => {
+            out.extend(format!("P6\n{} {}\n255\n", width, height).bytes());
+            out.extend(rows.iter().flatten().flat_map(|c| [c.r, c.g, c.b]));
+        }
+        ImageFormat::Png => {
+            let mut encoder = png::Encoder::new(&mut out, width as u32, height as u32);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let data = rows
+                .iter()
+                .flatten()
+                .flat_map(|c| [c.r, c.g, c.b])
+                .collect::<Vec<_>>();
+            encoder.write_header()?.write_image_data(&data)?;
+        }
+    }
+    Ok(out)
+}
+
+/// Write the grid to an image file, choosing the format from the file extension
+pub fn save<T>(
+    grid: &Grid<T>,
+    path: impl AsRef<Path>,
+    scale: usize,
+    colour: impl Fn(&T) -> Colour,
+) -> Result<(), Box<dyn Error>> {
+    let path = path.as_ref();
+    let format = ImageFormat::from_path(path)
+        .ok_or_else(|| format!("Don't know what image format to use for {}", path.display()))?;
+    fs::write(path, encode(grid, format, scale, colour)?)?;
+    Ok(())
+}
+
+impl Display {
+    /// Write the display to an image file, with lit pixels in black
+    pub fn save_image(&self, path: impl AsRef<Path>, scale: usize) -> Result<(), Box<dyn Error>> {
+        save(self.grid(), path, scale, |lit| {
+            if *lit {
+                Colour::BLACK
+            } else {
+                Colour::WHITE
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+fn checkerboard() -> Grid<bool> {
+    let mut grid = Grid::new(2, 2);
+    grid.set(0, 0, true).unwrap();
+    grid.set(1, 1, true).unwrap();
+    grid
+}
+
+#[cfg(test)]
+fn bw(b: &bool) -> Colour {
+    if *b {
+        Colour::BLACK
+    } else {
+        Colour::WHITE
+    }
+}
+
+#[test]
+fn test_encode_pbm() {
+    let encoded = encode(&checkerboard(), ImageFormat::Pbm, 3, bw).unwrap();
+    let (header, data) = encoded.split_at(b"P4\n6 6\n".len());
+    assert_eq!(header, b"P4\n6 6\n");
+    assert_eq!(
+        data,
+        &[0b11100000, 0b11100000, 0b11100000, 0b00011100, 0b00011100, 0b00011100]
+    );
+}
+
+#[test]
+fn test_encode_ppm_and_pgm() {
+    let mut grid = Grid::new(2, 1);
+    grid.set(1, 0, 2u8).unwrap();
+    let colour = |v: &u8| Colour::new(*v * 100, 0, 0);
+    assert_eq!(
+        encode(&grid, ImageFormat::Ppm, 1, colour).unwrap(),
+        b"P6\n2 1\n255\n\x00\x00\x00\xc8\x00\x00".to_vec()
+    );
+    assert_eq!(
+        encode(&grid, ImageFormat::Pgm, 1, colour).unwrap(),
+        b"P5\n2 1\n255\n\x00\x3b".to_vec()
+    );
+}
+
+#[test]
+fn test_encode_png_round_trip() {
+    let encoded = encode(&checkerboard(), ImageFormat::Png, 2, bw).unwrap();
+    let decoder = png::Decoder::new(encoded.as_slice());
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    assert_eq!((info.width, info.height), (4, 4));
+    assert_eq!(
+        &buf[0..12],
+        &[0, 0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255]
+    );
+}
+
+#[test]
+fn test_format_from_path() {
+    assert_eq!(
+        ImageFormat::from_path(Path::new("out/day14.PNG")),
+        Some(ImageFormat::Png)
+    );
+    assert_eq!(ImageFormat::from_path(Path::new("day8.jpeg")), None);
+}
@@ -0,0 +1,269 @@
+use std::{fmt, iter::FromIterator};
+
+/// A closed range of integers, which includes both its ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interval {
+    lower: i64,
+    upper: i64,
+}
+
+impl Interval {
+    /// The interval from lower to upper, or None if upper is below lower
+    pub fn new(lower: i64, upper: i64) -> Option<Self> {
+        (lower <= upper).then_some(Self { lower, upper })
+    }
+
+    pub fn point(x: i64) -> Self {
+        Self { lower: x, upper: x }
+    }
+
+    pub fn lower(&self) -> i64 {
+        self.lower
+    }
+
+    /// How many integers are in the interval
+    pub fn len(&self) -> u64 {
+        self.upper.abs_diff(self.lower) + 1
+    }
+
+    #[cfg(test)]
+    pub fn contains(&self, x: i64) -> bool {
+        self.lower <= x && x <= self.upper
+    }
+
+    pub fn contains_interval(&self, other: &Interval) -> bool {
+        self.lower <= other.lower && other.upper <= self.upper
+    }
+
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        self.lower <= other.upper && other.lower <= self.upper
+    }
+
+    #[cfg(test)]
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        Interval::new(self.lower.max(other.lower), self.upper.min(other.upper))
+    }
+
+    /// The single interval covering both, if they overlap or are next to each other
+    pub fn union(&self, other: &Interval) -> Option<Interval> {
+        let touching = self.lower <= other.upper.saturating_add(1)
+            && other.lower <= self.upper.saturating_add(1);
+        touching.then(|| Interval {
+            lower: self.lower.min(other.lower),
+            upper: self.upper.max(other.upper),
+        })
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.lower, self.upper)
+    }
+}
+
+/// A set of integers kept as the fewest intervals which cover it, in order, with gaps
+/// between each
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet(Vec<Interval>);
+
+impl IntervalSet {
+    #[cfg(test)]
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn intervals(&self) -> &[Interval] {
+        &self.0
+    }
+
+    /// How many integers the set covers
+    pub fn coverage(&self) -> u64 {
+        self.0.iter().map(Interval::len).sum()
+    }
+
+    /// The interval in the set which x is in, if any
+    #[cfg(test)]
+    fn interval_containing(&self, x: i64) -> Option<&Interval> {
+        let i = self.0.partition_point(|i| i.upper < x);
+        self.0.get(i).filter(|i| i.contains(x))
+    }
+
+    #[cfg(test)]
+    pub fn contains(&self, x: i64) -> bool {
+        self.interval_containing(x).is_some()
+    }
+
+    #[cfg(test)]
+    pub fn contains_interval(&self, other: &Interval) -> bool {
+        self.interval_containing(other.lower)
+            .is_some_and(|i| i.contains_interval(other))
+    }
+
+    #[cfg(test)]
+    pub fn insert(&mut self, interval: Interval) {
+        *self = self.union(&IntervalSet(vec![interval]));
+    }
+
+    #[cfg(test)]
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        // both are already in order, so merging them keeps them in order
+        let mut merged = Vec::with_capacity(self.0.len() + other.0.len());
+        let (mut a, mut b) = (self.0.iter().peekable(), other.0.iter().peekable());
+        while let Some(next) = match (a.peek(), b.peek()) {
+            (Some(x), Some(y)) if x.lower <= y.lower => a.next(),
+            (Some(_), Some(_)) => b.next(),
+            (Some(_), None) => a.next(),
+            (None, _) => b.next(),
+        } {
+            merged.push(*next);
+        }
+        IntervalSet(join_sorted(merged))
+    }
+
+    #[cfg(test)]
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.0.len() && j < other.0.len() {
+            if let Some(overlap) = self.0[i].intersection(&other.0[j]) {
+                result.push(overlap);
+            }
+            // whichever ends first can't overlap anything else in the other set
+            if self.0[i].upper < other.0[j].upper {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet(result)
+    }
+
+    /// Everything in this set which isn't in the other
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = Vec::new();
+        let mut j = 0;
+        for interval in &self.0 {
+            let mut remaining = Some(*interval);
+            // skip past everything in the other set which ends before this interval
+            while j < other.0.len() && other.0[j].upper < interval.lower {
+                j += 1;
+            }
+            let mut k = j;
+            while let (Some(current), Some(cut)) = (remaining, other.0.get(k)) {
+                if cut.lower > current.upper {
+                    break;
+                }
+                if cut.lower > current.lower {
+                    result.push(Interval {
+                        lower: current.lower,
+                        upper: cut.lower - 1,
+                    });
+                }
+                remaining = Interval::new(cut.upper.saturating_add(1), current.upper)
+                    .filter(|_| cut.upper < current.upper);
+                k += 1;
+            }
+            result.extend(remaining);
+        }
+        IntervalSet(result)
+    }
+}
+
+/// Joins up intervals which are in order of their lower ends wherever they overlap or
+/// touch
+fn join_sorted(sorted: Vec<Interval>) -> Vec<Interval> {
+    let mut joined: Vec<Interval> = Vec::with_capacity(sorted.len());
+    for interval in sorted {
+        match joined.last_mut().and_then(|last| last.union(&interval)) {
+            Some(union) => *joined.last_mut().expect("there was a union") = union,
+            None => joined.push(interval),
+        }
+    }
+    joined
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    /// Sorts the intervals then joins them up, so builds the set in O(n log n)
+    fn from_iter<I: IntoIterator<Item = Interval>>(iter: I) -> Self {
+        let mut intervals = iter.into_iter().collect::<Vec<_>>();
+        intervals.sort();
+        IntervalSet(join_sorted(intervals))
+    }
+}
+
+impl From<Interval> for IntervalSet {
+    fn from(interval: Interval) -> Self {
+        IntervalSet(vec![interval])
+    }
+}
+
+impl fmt::Display for IntervalSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, interval) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", interval)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn set(intervals: &[(i64, i64)]) -> IntervalSet {
+    intervals
+        .iter()
+        .map(|(l, u)| Interval::new(*l, *u).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_intervals() {
+    let a = Interval::new(2, 6).unwrap();
+    let b = Interval::new(4, 8).unwrap();
+    assert_eq!(Interval::new(3, 2), None);
+    assert_eq!(a.len(), 5);
+    assert!(a.overlaps(&b));
+    assert!(!a.contains_interval(&b));
+    assert!(a.contains_interval(&Interval::point(6)));
+    assert_eq!(a.intersection(&b), Interval::new(4, 6));
+    assert_eq!(a.union(&b), Interval::new(2, 8));
+    // touching intervals join up, but ones with a gap between don't
+    assert_eq!(a.union(&Interval::point(7)), Interval::new(2, 7));
+    assert_eq!(a.union(&Interval::point(8)), None);
+    assert_eq!(a.intersection(&Interval::point(8)), None);
+}
+
+#[test]
+fn test_normalise() {
+    let s = set(&[(5, 7), (-3, 1), (0, 2), (9, 9), (8, 8), (20, 30)]);
+    assert_eq!(s.to_string(), "-3-2,5-9,20-30");
+    assert_eq!(s.coverage(), 6 + 5 + 11);
+    assert!(s.contains(-3) && s.contains(9) && !s.contains(3) && !s.contains(31));
+    assert!(s.contains_interval(&Interval::new(5, 9).unwrap()));
+    assert!(!s.contains_interval(&Interval::new(2, 5).unwrap()));
+}
+
+#[test]
+fn test_set_operations() {
+    let a = set(&[(0, 10), (20, 30)]);
+    let b = set(&[(5, 22), (28, 40)]);
+    assert_eq!(a.union(&b).to_string(), "0-40");
+    assert_eq!(a.intersection(&b).to_string(), "5-10,20-22,28-30");
+    assert_eq!(a.difference(&b).to_string(), "0-4,23-27");
+    assert_eq!(b.difference(&a).to_string(), "11-19,31-40");
+    assert_eq!(a.difference(&a), IntervalSet::new());
+
+    let mut c = IntervalSet::new();
+    c.insert(Interval::point(3));
+    c.insert(Interval::point(1));
+    c.insert(Interval::point(2));
+    assert_eq!(c.to_string(), "1-3");
+
+    let holes = IntervalSet::from(Interval::new(0, 20).unwrap()).difference(&set(&[
+        (2, 3),
+        (5, 5),
+        (18, 25),
+    ]));
+    assert_eq!(holes.to_string(), "0-1,4-4,6-17");
+}
@@ -0,0 +1,427 @@
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    pub fn holds(&self, lhs: i32, rhs: i32) -> bool {
+        match self {
+            Comparison::Equal => lhs == rhs,
+            Comparison::NotEqual => lhs != rhs,
+            Comparison::Less => lhs < rhs,
+            Comparison::LessOrEqual => lhs <= rhs,
+            Comparison::Greater => lhs > rhs,
+            Comparison::GreaterOrEqual => lhs >= rhs,
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "==" | "=" => Ok(Comparison::Equal),
+            "!=" => Ok(Comparison::NotEqual),
+            "<" => Ok(Comparison::Less),
+            "<=" => Ok(Comparison::LessOrEqual),
+            ">" => Ok(Comparison::Greater),
+            ">=" => Ok(Comparison::GreaterOrEqual),
+            _ => Err(format!("'{}' is not a comparison", s)),
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// What an instruction's operand is allowed to be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandKind {
+    /// The name of a register, written `r` in a spec
+    Register,
+    /// A number, written `n`
+    Number,
+    /// A register or a number, written `v`
+    Value,
+    /// How far to jump relative to the instruction, written `t`
+    Target,
+}
+
+impl OperandKind {
+    pub fn accepts(&self, operand: &Operand) -> bool {
+        matches!(
+            (self, operand),
+            (OperandKind::Register, Operand::Register(_))
+                | (
+                    OperandKind::Number | OperandKind::Target,
+                    Operand::Number(_)
+                )
+                | (OperandKind::Value, _)
+        )
+    }
+}
+
+impl FromStr for OperandKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "r" => Ok(OperandKind::Register),
+            "n" => Ok(OperandKind::Number),
+            "v" => Ok(OperandKind::Value),
+            "t" => Ok(OperandKind::Target),
+            _ => Err(format!(
+                "'{}' is not an operand kind, expected r, n, v or t",
+                s
+            )),
+        }
+    }
+}
+
+/// An operand of an instruction in a program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    /// Index into the Cpu's registers
+    Register(usize),
+    Number(i32),
+}
+
+/// A register named by an effect, either directly or as one of the instruction's operands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterRef {
+    Fixed(usize),
+    Operand(usize),
+}
+
+/// A value used by an effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    Literal(i32),
+    Register(usize),
+    Operand(usize),
+}
+
+/// What an instruction does once its last cycle is reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    Nop,
+    Set(RegisterRef, Value),
+    Add(RegisterRef, Value),
+    Mul(RegisterRef, Value),
+    /// Move the program counter by the value, relative to this instruction
+    Jump(Value),
+    /// Jump by the last value if the comparison of the first two holds
+    JumpIf(Comparison, Value, Value, Value),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opcode {
+    pub mnemonic: String,
+    pub operands: Vec<OperandKind>,
+    /// How many cycles the instruction takes, with the effect happening in the last one
+    pub cycles: usize,
+    pub effect: Effect,
+}
+
+/// An instruction in a program, pointing into its Isa's table of opcodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: usize,
+    pub operands: Vec<Operand>,
+}
+
+/// A table-driven instruction set: the registers a Cpu has and the opcodes it runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Isa {
+    registers: Vec<String>,
+    initial_values: Vec<i32>,
+    opcodes: Vec<Opcode>,
+}
+
+/// The spec for the handheld device from day 10
+pub const HANDHELD_SPEC: &str = "registers: x=1
+noop: cycles 1, nop
+addx n: cycles 2, add x $0";
+
+impl Isa {
+    pub fn handheld() -> Self {
+        HANDHELD_SPEC.parse().expect("the built in spec is valid")
+    }
+
+    pub fn registers(&self) -> &[String] {
+        &self.registers
+    }
+
+    /// What each register holds when a program starts
+    pub fn initial_values(&self) -> &[i32] {
+        &self.initial_values
+    }
+
+    pub fn register_index(&self, name: &str) -> Option<usize> {
+        self.registers.iter().position(|r| r == name)
+    }
+
+    pub fn opcode(&self, index: usize) -> &Opcode {
+        &self.opcodes[index]
+    }
+
+    pub fn opcode_index(&self, mnemonic: &str) -> Option<usize> {
+        self.opcodes.iter().position(|o| o.mnemonic == mnemonic)
+    }
+
+    /// Make an instruction from its mnemonic and operand words, as written in source
+    #[cfg(test)]
+    pub fn instruction(&self, mnemonic: &str, operands: &[&str]) -> Result<Instruction, String> {
+        let opcode = self
+            .opcode_index(mnemonic)
+            .ok_or_else(|| format!("Instruction '{}' not recognised", mnemonic))?;
+        let kinds = &self.opcodes[opcode].operands;
+        if kinds.len() != operands.len() {
+            return Err(format!(
+                "{} takes {} operands but was given {}",
+                mnemonic,
+                kinds.len(),
+                operands.len()
+            ));
+        }
+        let operands = kinds
+            .iter()
+            .zip(operands)
+            .map(|(kind, word)| {
+                let operand = self.operand(word)?;
+                if kind.accepts(&operand) {
+                    Ok(operand)
+                } else {
+                    Err(format!("'{}' can't be used as a {:?} operand", word, kind))
+                }
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Instruction { opcode, operands })
+    }
+
+    /// Read one operand word, which is a register name or a number
+    pub fn operand(&self, word: &str) -> Result<Operand, String> {
+        if let Some(r) = self.register_index(word) {
+            Ok(Operand::Register(r))
+        } else {
+            word.parse()
+                .map(Operand::Number)
+                .map_err(|_| format!("'{}' is neither a register nor a number", word))
+        }
+    }
+
+    /// Write an instruction back out the way it would be written in source
+    pub fn disassemble(&self, instruction: &Instruction) -> String {
+        let mut s = self.opcodes[instruction.opcode].mnemonic.clone();
+        for operand in &instruction.operands {
+            match operand {
+                Operand::Register(r) => s += &format!(" {}", self.registers[*r]),
+                Operand::Number(n) => s += &format!(" {}", n),
+            }
+        }
+        s
+    }
+
+    fn parse_opcode(&self, line: &str) -> Result<Opcode, String> {
+        let (head, body) = line.split_once(':').ok_or_else(|| {
+            format!(
+                "Expected 'mnemonic operands: cycles n, effect' in '{}'",
+                line
+            )
+        })?;
+        let mut head = head.split_whitespace();
+        let mnemonic = head
+            .next()
+            .ok_or_else(|| format!("No mnemonic in '{}'", line))?
+            .to_owned();
+        let operands = head.map(str::parse).collect::<Result<Vec<_>, _>>()?;
+
+        let (cycles, effect) = body
+            .split_once(',')
+            .ok_or_else(|| format!("Expected a cycle count and an effect for {}", mnemonic))?;
+        let cycles = cycles
+            .trim()
+            .strip_prefix("cycles ")
+            .and_then(|n| n.trim().parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .ok_or_else(|| format!("{} needs a cycle count of at least 1", mnemonic))?;
+        let effect = self.parse_effect(effect, &operands)?;
+        Ok(Opcode {
+            mnemonic,
+            operands,
+            cycles,
+            effect,
+        })
+    }
+
+    fn parse_effect(&self, effect: &str, operands: &[OperandKind]) -> Result<Effect, String> {
+        let words = effect.split_whitespace().collect::<Vec<_>>();
+        let value = |w: &str| self.parse_value(w, operands);
+        let register = |w: &str| self.parse_register_ref(w, operands);
+        match words.as_slice() {
+            ["nop"] => Ok(Effect::Nop),
+            ["set", r, v] => Ok(Effect::Set(register(r)?, value(v)?)),
+            ["add", r, v] => Ok(Effect::Add(register(r)?, value(v)?)),
+            ["mul", r, v] => Ok(Effect::Mul(register(r)?, value(v)?)),
+            ["jump", v] => Ok(Effect::Jump(value(v)?)),
+            ["jump-if", cmp, a, b, v] => Ok(Effect::JumpIf(
+                cmp.parse()?,
+                value(a)?,
+                value(b)?,
+                value(v)?,
+            )),
+            _ => Err(format!("Effect '{}' not recognised", effect.trim())),
+        }
+    }
+
+    fn parse_value(&self, word: &str, operands: &[OperandKind]) -> Result<Value, String> {
+        if let Some(i) = word.strip_prefix('$') {
+            Ok(Value::Operand(operand_index(i, operands)?))
+        } else if let Some(r) = self.register_index(word) {
+            Ok(Value::Register(r))
+        } else {
+            word.parse()
+                .map(Value::Literal)
+                .map_err(|_| format!("'{}' is not a value", word))
+        }
+    }
+
+    fn parse_register_ref(
+        &self,
+        word: &str,
+        operands: &[OperandKind],
+    ) -> Result<RegisterRef, String> {
+        if let Some(i) = word.strip_prefix('$') {
+            let i = operand_index(i, operands)?;
+            if operands[i] != OperandKind::Register {
+                return Err(format!(
+                    "Operand ${} is written to, so must be a register",
+                    i
+                ));
+            }
+            Ok(RegisterRef::Operand(i))
+        } else {
+            self.register_index(word)
+                .map(RegisterRef::Fixed)
+                .ok_or_else(|| format!("'{}' is not a register", word))
+        }
+    }
+}
+
+fn operand_index(i: &str, operands: &[OperandKind]) -> Result<usize, String> {
+    i.parse::<usize>()
+        .ok()
+        .filter(|i| *i < operands.len())
+        .ok_or_else(|| format!("${} is not one of the {} operands", i, operands.len()))
+}
+
+impl FromStr for Isa {
+    type Err = String;
+
+    /// Parse a spec with a line listing the registers, optionally with starting values
+    /// like `x=1`, then one line per opcode, such as `jnz r t: cycles 2, jump-if != $0 0 $1`.
+    /// Lines starting with # are comments.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'));
+        let (registers, initial_values) = lines
+            .next()
+            .and_then(|l| l.strip_prefix("registers:"))
+            .ok_or("A spec must start with a 'registers:' line")?
+            .split_whitespace()
+            .map(|r| match r.split_once('=') {
+                Some((name, value)) => value
+                    .parse()
+                    .map(|v| (name.to_owned(), v))
+                    .map_err(|_| format!("Register {} has a bad starting value", name)),
+                None => Ok((r.to_owned(), 0)),
+            })
+            .collect::<Result<(Vec<_>, Vec<_>), _>>()?;
+        if registers.is_empty() {
+            return Err("A spec needs at least one register".to_owned());
+        }
+        for (i, name) in registers.iter().enumerate() {
+            if registers[..i].contains(name) {
+                return Err(format!("Register {} is listed more than once", name));
+            }
+        }
+        let mut isa = Isa {
+            registers,
+            initial_values,
+            opcodes: Vec::new(),
+        };
+        for line in lines {
+            let opcode = isa.parse_opcode(line)?;
+            if isa.opcode_index(&opcode.mnemonic).is_some() {
+                return Err(format!("{} is defined more than once", opcode.mnemonic));
+            }
+            isa.opcodes.push(opcode);
+        }
+        Ok(isa)
+    }
+}
+
+#[test]
+fn test_handheld_spec() {
+    let isa = Isa::handheld();
+    assert_eq!(isa.registers(), &["x".to_owned()]);
+    assert_eq!(isa.initial_values(), &[1]);
+    let addx = isa.opcode(isa.opcode_index("addx").unwrap());
+    assert_eq!(addx.cycles, 2);
+    assert_eq!(
+        addx.effect,
+        Effect::Add(RegisterRef::Fixed(0), Value::Operand(0))
+    );
+    let instruction = isa.instruction("addx", &["-5"]).unwrap();
+    assert_eq!(isa.disassemble(&instruction), "addx -5");
+    assert!(isa.instruction("addx", &["x"]).is_err());
+    assert!(isa.instruction("addx", &[]).is_err());
+    assert!(isa.instruction("jmp", &["1"]).is_err());
+}
+
+#[test]
+fn test_spec_errors() {
+    assert!("noop: cycles 1, nop".parse::<Isa>().is_err());
+    assert!("registers: a\ninc r: cycles 0, add $0 1"
+        .parse::<Isa>()
+        .is_err());
+    assert!("registers: a\ninc n: cycles 1, add $0 1"
+        .parse::<Isa>()
+        .is_err());
+    assert!("registers: a\ninc r: cycles 1, add $1 1"
+        .parse::<Isa>()
+        .is_err());
+    assert!("registers: a\nnop: cycles 1, nop\nnop: cycles 1, nop"
+        .parse::<Isa>()
+        .is_err());
+}
+
+#[test]
+fn test_duplicate_registers() {
+    assert_eq!(
+        "registers: x=1 x=2".parse::<Isa>().err(),
+        Some("Register x is listed more than once".to_owned())
+    );
+    assert!("registers: x y x".parse::<Isa>().is_err());
+}
@@ -1,4 +1,20 @@
+pub mod assembler;
+pub mod automaton;
+pub mod bitgrid;
 pub mod cpu;
 pub mod day;
+pub mod debugger;
 pub mod display;
+pub mod flood;
 pub mod grid;
+pub mod growable;
+pub mod image;
+pub mod interval;
+pub mod isa;
+pub mod ocr;
+pub mod peripheral;
+pub mod raycast;
+pub mod serialise;
+pub mod terminal;
+pub mod trace;
+pub mod voxel;
@@ -0,0 +1,127 @@
+use super::display::Display;
+
+const GLYPH_WIDTH: usize = 4;
+const GLYPH_HEIGHT: usize = 6;
+/// Glyphs are separated by a single blank column
+const GLYPH_STRIDE: usize = GLYPH_WIDTH + 1;
+
+/// The 4x6 capital letter font used by the puzzles which draw text on a screen
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// The result of reading text off a display
+#[derive(Debug, PartialEq, Eq)]
+pub struct RecognisedText {
+    /// The decoded letters, with `?` standing in for any glyph which wasn't recognised
+    pub text: String,
+    /// The x coordinate of the left edge of every glyph which wasn't recognised
+    pub unrecognised: Vec<usize>,
+}
+
+impl RecognisedText {
+    pub fn is_complete(&self) -> bool {
+        self.unrecognised.is_empty()
+    }
+}
+
+impl Display {
+    /// Read capital letters off the top of the display, assuming they are
+    /// drawn in the standard font starting from the left hand edge
+    pub fn recognise_text(&self) -> RecognisedText {
+        let glyph_count = (self.grid().width() + 1) / GLYPH_STRIDE;
+        let mut text = String::new();
+        let mut unrecognised = Vec::new();
+
+        for left in (0..glyph_count).map(|g| g * GLYPH_STRIDE) {
+            let pixels = self.glyph_at(left);
+            if pixels.iter().all(|row| row.iter().all(|p| !p)) {
+                text.push(' ');
+            } else if let Some(c) = recognise_glyph(&pixels) {
+                text.push(c);
+            } else {
+                text.push('?');
+                unrecognised.push(left);
+            }
+        }
+
+        RecognisedText {
+            text: text.trim_end().to_owned(),
+            unrecognised,
+        }
+    }
+
+    fn glyph_at(&self, left: usize) -> [[bool; GLYPH_WIDTH]; GLYPH_HEIGHT] {
+        let mut pixels = [[false; GLYPH_WIDTH]; GLYPH_HEIGHT];
+        for (y, row) in pixels.iter_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = *self.grid().get(left + x, y).unwrap_or(&false);
+            }
+        }
+        pixels
+    }
+}
+
+fn recognise_glyph(pixels: &[[bool; GLYPH_WIDTH]; GLYPH_HEIGHT]) -> Option<char> {
+    FONT.iter()
+        .find(|(_, rows)| {
+            rows.iter().zip(pixels.iter()).all(|(expected, actual)| {
+                expected
+                    .chars()
+                    .zip(actual.iter())
+                    .all(|(e, a)| (e == '#') == *a)
+            })
+        })
+        .map(|(c, _)| *c)
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn test_recognise_text() {
+    let display = Display::from_str(
+        "###..#..#.####..##..
+#..#.#..#.#....#..#.
+#..#.####.###..#....
+###..#..#.#....#.##.
+#.#..#..#.#....#..#.
+#..#.#..#.####..###.",
+    )
+    .unwrap();
+    let result = display.recognise_text();
+    assert_eq!(result.text, "RHEG");
+    assert!(result.is_complete());
+}
+
+#[test]
+fn test_recognise_unknown_glyph() {
+    let display = Display::from_str(
+        "#..#.#.#.
+#..#.....
+####.#.#.
+#..#.....
+#..#.#.#.
+#..#.....",
+    )
+    .unwrap();
+    let result = display.recognise_text();
+    assert_eq!(result.text, "H?");
+    assert_eq!(result.unrecognised, vec![5]);
+}
@@ -0,0 +1,153 @@
+use super::display::Display;
+
+/// A device attached to a Cpu, which gets to look at the registers during every cycle
+pub trait Peripheral {
+    /// Called at the start of each cycle, numbered from 1, with the registers as they
+    /// are during that cycle
+    fn on_cycle(&mut self, cycle: usize, registers: &[i32]);
+}
+
+/// Records the value of a register at chosen cycles, to work out signal strength
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignalSampler {
+    sample_points: Vec<usize>,
+    register: usize,
+    samples: Vec<(usize, i32)>,
+}
+
+impl SignalSampler {
+    /// Sample the first register at each of the given cycles
+    pub fn new(sample_points: impl IntoIterator<Item = usize>) -> Self {
+        Self {
+            sample_points: sample_points.into_iter().collect(),
+            register: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Sample at first, then every step cycles after it up to and including last.
+    /// None if step is zero.
+    pub fn every(first: usize, step: usize, last: usize) -> Option<Self> {
+        (step > 0).then(|| Self::new((first..=last).step_by(step)))
+    }
+
+    /// Sample this register instead of the first one. If the Cpu doesn't have this
+    /// register, nothing is sampled.
+    #[cfg(test)]
+    pub fn register(mut self, register: usize) -> Self {
+        self.register = register;
+        self
+    }
+
+    /// The cycle and register value at every sample point reached so far
+    #[cfg(test)]
+    pub fn samples(&self) -> &[(usize, i32)] {
+        &self.samples
+    }
+
+    /// The sum of each sampled cycle number multiplied by the value during it
+    pub fn strength(&self) -> i32 {
+        self.samples
+            .iter()
+            .map(|(cycle, value)| *cycle as i32 * value)
+            .sum()
+    }
+}
+
+impl Peripheral for SignalSampler {
+    fn on_cycle(&mut self, cycle: usize, registers: &[i32]) {
+        if self.sample_points.contains(&cycle) {
+            if let Some(value) = registers.get(self.register) {
+                self.samples.push((cycle, *value));
+            }
+        }
+    }
+}
+
+/// A screen whose beam draws one pixel per cycle, left to right and top to bottom.
+/// The pixel is lit if it's under the three pixel wide sprite centred on the first
+/// register. Cycles after the last pixel draw nothing, and nothing is lit if the Cpu
+/// has no registers.
+pub struct Crt {
+    display: Display,
+    width: usize,
+    height: usize,
+}
+
+impl Crt {
+    /// None if width is zero, as the beam would never get off the first row
+    pub fn new(width: usize, height: usize) -> Option<Self> {
+        (width > 0).then(|| Self {
+            display: Display::new(width, height),
+            width,
+            height,
+        })
+    }
+
+    pub fn display(&self) -> &Display {
+        &self.display
+    }
+}
+
+impl Peripheral for Crt {
+    fn on_cycle(&mut self, cycle: usize, registers: &[i32]) {
+        // cycles count from 1, so there's nothing to draw for cycle 0
+        let beam = match cycle.checked_sub(1) {
+            Some(beam) => beam,
+            None => return,
+        };
+        let (x, y) = (beam % self.width, beam / self.width);
+        if y < self.height {
+            let lit = registers
+                .first()
+                .is_some_and(|sprite| (x as i32 - sprite).abs() <= 1);
+            self.display
+                .set(x, y, lit)
+                .expect("beam position is within the display");
+        }
+    }
+}
+
+#[test]
+fn test_sampler() {
+    let mut sampler = SignalSampler::every(2, 3, 8).unwrap();
+    for (cycle, x) in (1..=10).zip([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]) {
+        sampler.on_cycle(cycle, &[x, -x]);
+    }
+    assert_eq!(sampler.samples(), &[(2, 2), (5, 5), (8, 8)]);
+    assert_eq!(sampler.strength(), 4 + 25 + 64);
+
+    let mut sampler = SignalSampler::new([3]).register(1);
+    sampler.on_cycle(3, &[1, 7]);
+    assert_eq!(sampler.strength(), 21);
+}
+
+#[test]
+fn test_crt() {
+    let mut crt = Crt::new(4, 1).unwrap();
+    for (cycle, x) in (1..=6).zip([0, 0, 0, 3, 3, 3]) {
+        crt.on_cycle(cycle, &[x]);
+    }
+    assert_eq!(crt.display().to_string(), "##.#\n");
+}
+
+#[test]
+fn test_sampler_edge_cases() {
+    assert!(SignalSampler::every(1, 0, 10).is_none());
+
+    let mut sampler = SignalSampler::new([0, 3]).register(2);
+    sampler.on_cycle(0, &[1, 2]);
+    sampler.on_cycle(3, &[1, 2]);
+    assert_eq!(sampler.samples(), &[]);
+}
+
+#[test]
+fn test_crt_edge_cases() {
+    assert!(Crt::new(0, 6).is_none());
+
+    let mut crt = Crt::new(2, 1).unwrap();
+    crt.on_cycle(0, &[0]);
+    crt.on_cycle(1, &[]);
+    crt.on_cycle(2, &[1]);
+    assert_eq!(crt.display().to_string(), ".#\n");
+}
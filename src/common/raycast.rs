@@ -0,0 +1,245 @@
+use super::grid::{Grid, GridOperationError};
+
+/// The four directions along the grid's axes, as (dx, dy) with y increasing downwards
+pub const CARDINALS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+/// Walks across a grid from a starting cell, one step of a direction at a time,
+/// yielding every cell after the start until it leaves the grid
+#[cfg(test)]
+pub struct Ray<'a, T> {
+    grid: &'a Grid<T>,
+    position: (usize, usize),
+    direction: (isize, isize),
+}
+
+#[cfg(test)]
+impl<'a, T> Iterator for Ray<'a, T> {
+    type Item = ((usize, usize), &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.direction == (0, 0) {
+            return None;
+        }
+        let x = self.position.0.checked_add_signed(self.direction.0)?;
+        let y = self.position.1.checked_add_signed(self.direction.1)?;
+        let t = self.grid.get(x, y).ok()?;
+        self.position = (x, y);
+        Some(((x, y), t))
+    }
+}
+
+/// What a ray cast found
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit {
+    /// Number of cells walked, including the one which stopped the ray
+    pub distance: usize,
+    /// The cell which stopped the ray, or None if it reached the edge of the grid
+    pub stopped_at: Option<(usize, usize)>,
+}
+
+/// How far a cell can see along one of the cardinal directions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Sightline {
+    /// Number of cells seen, including the one which blocked the view
+    pub distance: usize,
+    /// True if nothing blocked the view before the edge of the grid
+    pub reaches_edge: bool,
+}
+
+impl<T> Grid<T> {
+    /// Every cell after (x, y) along the direction vector, which may be diagonal or
+    /// longer than one step
+    #[cfg(test)]
+    pub fn ray(
+        &self,
+        x: usize,
+        y: usize,
+        direction: (isize, isize),
+    ) -> Result<Ray<'_, T>, GridOperationError> {
+        self.get(x, y)?;
+        Ok(Ray {
+            grid: self,
+            position: (x, y),
+            direction,
+        })
+    }
+
+    /// Walk from (x, y) along the direction until stop is true for a cell or the
+    /// ray leaves the grid
+    #[cfg(test)]
+    pub fn cast(
+        &self,
+        x: usize,
+        y: usize,
+        direction: (isize, isize),
+        stop: impl Fn(&T) -> bool,
+    ) -> Result<Hit, GridOperationError> {
+        let mut distance = 0;
+        for (p, t) in self.ray(x, y, direction)? {
+            distance += 1;
+            if stop(t) {
+                return Ok(Hit {
+                    distance,
+                    stopped_at: Some(p),
+                });
+            }
+        }
+        Ok(Hit {
+            distance,
+            stopped_at: None,
+        })
+    }
+
+    /// For every cell, how far it can see along a cardinal direction before a cell
+    /// whose key is at least as large as its own blocks the view. Each row or column
+    /// is done in a single pass with a monotonic stack, so this is linear in the
+    /// size of the grid.
+    pub fn sightlines<K: Ord>(
+        &self,
+        direction: (isize, isize),
+        key: impl Fn(&T) -> K,
+    ) -> Result<Grid<Sightline>, GridOperationError> {
+        let (w, h) = (self.width(), self.height());
+        // each line is walked starting from the edge the cells are looking towards
+        let lines: Vec<Vec<(usize, usize)>> = match direction {
+            (-1, 0) => (0..h).map(|y| (0..w).map(|x| (x, y)).collect()).collect(),
+            (1, 0) => (0..h)
+                .map(|y| (0..w).rev().map(|x| (x, y)).collect())
+                .collect(),
+            (0, -1) => (0..w).map(|x| (0..h).map(|y| (x, y)).collect()).collect(),
+            (0, 1) => (0..w)
+                .map(|x| (0..h).rev().map(|y| (x, y)).collect())
+                .collect(),
+            _ => return Err(GridOperationError::NotCardinal(direction.0, direction.1)),
+        };
+
+        let mut result = self.map(|_| Sightline::default());
+        for line in lines {
+            // indexes into line of cells not yet known to be hidden behind a taller one,
+            // with non-increasing keys from the bottom of the stack. A cell with an equal
+            // key stays on so that it blocks the view of the next one.
+            let mut stack: Vec<(usize, K)> = Vec::new();
+            for (i, (x, y)) in line.iter().enumerate() {
+                let k = key(&self[(*x, *y)]);
+                while stack.last().map(|(_, top)| *top < k).unwrap_or(false) {
+                    stack.pop();
+                }
+                result[(*x, *y)] = match stack.last() {
+                    Some((blocker, _)) => Sightline {
+                        distance: i - blocker,
+                        reaches_edge: false,
+                    },
+                    None => Sightline {
+                        distance: i,
+                        reaches_edge: true,
+                    },
+                };
+                stack.push((i, k));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Which cells can be seen from outside the grid along at least one row or column,
+    /// because every cell between them and the edge has a smaller key
+    pub fn visible_from_edges<K: Ord>(&self, key: impl Fn(&T) -> K) -> Grid<bool> {
+        let mut visible = self.map(|_| false);
+        for direction in CARDINALS {
+            let sightlines = self
+                .sightlines(direction, &key)
+                .expect("CARDINALS only has cardinal directions");
+            visible = visible
+                .zip_with(&sightlines, |v, s| *v || s.reaches_edge)
+                .expect("all grids here are the same size");
+        }
+        visible
+    }
+}
+
+#[cfg(test)]
+fn heights() -> Grid<u8> {
+    let rows = ["30373", "25512", "65332", "33549", "35390"];
+    let mut grid = Grid::new(5, 5);
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            grid[(x, y)] = c.to_digit(10).unwrap() as u8;
+        }
+    }
+    grid
+}
+
+#[test]
+fn test_ray_diagonal() {
+    let grid = heights();
+    let cells = grid
+        .ray(1, 1, (1, 1))
+        .unwrap()
+        .map(|(p, t)| (p, *t))
+        .collect::<Vec<_>>();
+    assert_eq!(cells, vec![((2, 2), 3), ((3, 3), 4), ((4, 4), 0)]);
+    assert_eq!(grid.ray(0, 0, (-1, 1)).unwrap().count(), 0);
+    assert_eq!(grid.ray(0, 0, (2, 0)).unwrap().count(), 2);
+    assert!(grid.ray(5, 0, (1, 0)).is_err());
+}
+
+#[test]
+fn test_cast() {
+    let grid = heights();
+    let hit = grid.cast(2, 3, (0, -1), |t| *t >= 5).unwrap();
+    assert_eq!(
+        hit,
+        Hit {
+            distance: 2,
+            stopped_at: Some((2, 1))
+        }
+    );
+    let miss = grid.cast(2, 3, (1, -1), |t| *t >= 5).unwrap();
+    assert_eq!(
+        miss,
+        Hit {
+            distance: 2,
+            stopped_at: None
+        }
+    );
+}
+
+#[test]
+fn test_sightlines_match_casting() {
+    let grid = heights();
+    for direction in CARDINALS {
+        let sightlines = grid.sightlines(direction, |t| *t).unwrap();
+        for (x, y) in grid.iter_coords() {
+            let h = grid[(x, y)];
+            let hit = grid.cast(x, y, direction, |t| *t >= h).unwrap();
+            assert_eq!(sightlines[(x, y)].distance, hit.distance);
+            assert_eq!(sightlines[(x, y)].reaches_edge, hit.stopped_at.is_none());
+        }
+    }
+    assert!(grid.sightlines((1, 1), |t| *t).is_err());
+}
+
+#[test]
+fn test_sightlines_with_equal_keys() {
+    let mut grid = Grid::new(5, 1);
+    for (x, t) in [5u8, 3, 3, 3, 4].into_iter().enumerate() {
+        grid[(x, 0)] = t;
+    }
+    let left = grid.sightlines((-1, 0), |t| *t).unwrap();
+    let distances = (0..5).map(|x| left[(x, 0)].distance).collect::<Vec<_>>();
+    assert_eq!(distances, vec![0, 1, 1, 1, 4]);
+    assert!(!left[(2, 0)].reaches_edge);
+    let right = grid.sightlines((1, 0), |t| *t).unwrap();
+    let distances = (0..5).map(|x| right[(x, 0)].distance).collect::<Vec<_>>();
+    assert_eq!(distances, vec![4, 1, 1, 1, 0]);
+    assert!(!right[(1, 0)].reaches_edge);
+    assert!(right[(0, 0)].reaches_edge);
+}
+
+#[test]
+fn test_visible_from_edges() {
+    let grid = heights();
+    let visible = grid.visible_from_edges(|t| *t);
+    assert_eq!(visible.count(|v| *v), 21);
+    assert!(!visible[(3, 1)]);
+}
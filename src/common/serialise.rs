@@ -0,0 +1,198 @@
+use super::display::Display;
+use super::grid::Grid;
+#[cfg(test)]
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+#[cfg(test)]
+use std::{fs, path::Path};
+
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// JSON, for looking at by eye and keeping test fixtures in
+    Text,
+    /// MessagePack, for checkpointing big states quickly
+    Binary,
+}
+
+#[cfg(test)]
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" => Some(Format::Text),
+            "msgpack" | "bin" => Some(Format::Binary),
+            _ => None,
+        }
+    }
+}
+
+pub fn to_text<T: Serialize>(value: &T) -> Result<String, Box<dyn Error>> {
+    Ok(serde_json::to_string_pretty(value)?)
+}
+
+#[cfg(test)]
+pub fn from_text<T: DeserializeOwned>(text: &str) -> Result<T, Box<dyn Error>> {
+    Ok(serde_json::from_str(text)?)
+}
+
+#[cfg(test)]
+pub fn to_binary<T: Serialize>(value: &T) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(rmp_serde::to_vec_named(value)?)
+}
+
+#[cfg(test)]
+pub fn from_binary<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Box<dyn Error>> {
+    Ok(rmp_serde::from_slice(bytes)?)
+}
+
+/// Write the value to a file, choosing the format from the file extension
+#[cfg(test)]
+pub fn save<T: Serialize>(value: &T, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+    let path = path.as_ref();
+    match format_of(path)? {
+        Format::Text => fs::write(path, to_text(value)?)?,
+        Format::Binary => fs::write(path, to_binary(value)?)?,
+    }
+    Ok(())
+}
+
+/// Read a value back from a file written by save
+#[cfg(test)]
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, Box<dyn Error>> {
+    let path = path.as_ref();
+    match format_of(path)? {
+        Format::Text => from_text(&fs::read_to_string(path)?),
+        Format::Binary => from_binary(&fs::read(path)?),
+    }
+}
+
+#[cfg(test)]
+fn format_of(path: &Path) -> Result<Format, Box<dyn Error>> {
+    Format::from_path(path)
+        .ok_or_else(|| format!("Don't know what format to use for {}", path.display()).into())
+}
+
+/// The fields of a Grid as they're stored, checked for consistency before becoming one
+#[derive(Deserialize)]
+pub(super) struct GridData<T> {
+    content: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> TryFrom<GridData<T>> for Grid<T> {
+    type Error = String;
+
+    fn try_from(data: GridData<T>) -> Result<Self, Self::Error> {
+        Grid::from_content(data.content, data.width, data.height)
+    }
+}
+
+/// A display is stored as the same rows of `#` and `.` that it prints as
+impl Serialize for Display {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Display {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+fn sample_grid() -> Grid<u8> {
+    let mut grid = Grid::new(3, 2);
+    grid[(0, 0)] = 7;
+    grid[(2, 1)] = 9;
+    grid
+}
+
+#[test]
+fn test_grid_round_trips() {
+    let grid = sample_grid();
+    let text = to_text(&grid).unwrap();
+    assert_eq!(from_text::<Grid<u8>>(&text).unwrap(), grid);
+    let bytes = to_binary(&grid).unwrap();
+    assert_eq!(from_binary::<Grid<u8>>(&bytes).unwrap(), grid);
+}
+
+#[test]
+fn test_grid_rejects_wrong_size() {
+    let result = from_text::<Grid<u8>>(r#"{"content": [1, 2, 3], "width": 2, "height": 2}"#);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_grid3_round_trips() {
+    use super::voxel::{BoundingBox, Grid3, Point3};
+
+    let mut grid = Grid3::new(BoundingBox::new(
+        Point3::new(-1, 0, 2),
+        Point3::new(1, 1, 3),
+    ));
+    grid.set(&Point3::new(-1, 1, 3), 5u8).unwrap();
+    let text = to_text(&grid).unwrap();
+    assert_eq!(from_text::<Grid3<u8>>(&text).unwrap(), grid);
+    let bytes = to_binary(&grid).unwrap();
+    assert_eq!(from_binary::<Grid3<u8>>(&bytes).unwrap(), grid);
+}
+
+#[test]
+fn test_grid3_rejects_wrong_size() {
+    use super::voxel::Grid3;
+
+    let too_few = r#"{"content": [1, 2, 3], "bounds": {"min": {"x": 0, "y": 0, "z": 0}, "max": {"x": 1, "y": 1, "z": 0}}}"#;
+    assert!(from_text::<Grid3<u8>>(too_few).is_err());
+    let inside_out = r#"{"content": [], "bounds": {"min": {"x": 1, "y": 0, "z": 0}, "max": {"x": 0, "y": 0, "z": 0}}}"#;
+    assert!(from_text::<Grid3<u8>>(inside_out).is_err());
+}
+
+#[test]
+fn test_growable_grid_round_trips() {
+    use super::growable::{GrowableGrid, Growth};
+
+    let mut grid = GrowableGrid::new(Growth::Chunked(3));
+    grid.set(-4, 7, 2u8).unwrap();
+    grid.set(1, 5, 3).unwrap();
+    let mut restored = from_text::<GrowableGrid<u8>>(&to_text(&grid).unwrap()).unwrap();
+    assert_eq!(restored, grid);
+    assert_eq!(
+        from_binary::<GrowableGrid<u8>>(&to_binary(&grid).unwrap()).unwrap(),
+        grid
+    );
+    // the growth policy comes back too
+    restored.set(10, 5, 1).unwrap();
+    grid.set(10, 5, 1).unwrap();
+    assert_eq!(restored.bounds(), grid.bounds());
+}
+
+#[test]
+fn test_display_is_stored_as_text() {
+    let display: Display = "#..\n.#.\n".parse().unwrap();
+    let text = to_text(&display).unwrap();
+    assert_eq!(text, r##""#..\n.#.\n""##);
+    assert_eq!(
+        from_binary::<Display>(&to_binary(&display).unwrap())
+            .unwrap()
+            .to_string(),
+        display.to_string()
+    );
+}
+
+#[test]
+fn test_save_and_load() {
+    let dir = std::env::temp_dir().join(format!("aoc-serialise-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for name in ["grid.json", "grid.msgpack"] {
+        let path = dir.join(name);
+        save(&sample_grid(), &path).unwrap();
+        assert_eq!(load::<Grid<u8>>(&path).unwrap(), sample_grid());
+    }
+    assert!(save(&sample_grid(), dir.join("grid.txt")).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
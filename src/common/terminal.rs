@@ -0,0 +1,206 @@
+use super::display::Display;
+use super::grid::Grid;
+use super::image::Colour;
+use std::fmt::Write;
+
+/// The part of a grid to draw, in grid cells from the top left, limited to
+/// a number of terminal columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub left: usize,
+    pub top: usize,
+    pub columns: usize,
+    pub rows: Option<usize>,
+}
+
+impl Viewport {
+    pub fn new(left: usize, top: usize, columns: usize, rows: Option<usize>) -> Self {
+        Self {
+            left,
+            top,
+            columns,
+            rows,
+        }
+    }
+
+    /// The whole grid, clipped to the width of the terminal as reported by COLUMNS,
+    /// or 80 columns if that isn't available
+    pub fn terminal() -> Self {
+        let columns = std::env::var("COLUMNS")
+            .ok()
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(80);
+        Self::new(0, 0, columns, None)
+    }
+
+    /// The grid cells covered when each terminal character holds char_width x char_height cells
+    fn clip<T>(
+        &self,
+        grid: &Grid<T>,
+        char_width: usize,
+        char_height: usize,
+    ) -> (usize, usize, usize, usize) {
+        let right = usize::min(grid.width(), self.left + self.columns * char_width);
+        let bottom = match self.rows {
+            Some(rows) => usize::min(grid.height(), self.top + rows * char_height),
+            None => grid.height(),
+        };
+        (self.left, self.top, right, bottom)
+    }
+}
+
+/// Whether the user asked for days to draw their grids in the terminal by setting AOC_DRAW
+pub fn wants_drawing() -> bool {
+    std::env::var_os("AOC_DRAW").is_some()
+}
+
+fn cell_is_set<T>(grid: &Grid<T>, x: usize, y: usize, is_set: &impl Fn(&T) -> bool) -> bool {
+    grid.get(x, y).map(is_set).unwrap_or(false)
+}
+
+/// Draw the grid with each braille character covering 2x4 cells
+pub fn render_braille<T>(
+    grid: &Grid<T>,
+    viewport: &Viewport,
+    is_set: impl Fn(&T) -> bool,
+) -> String {
+    // bit for each dot, indexed by [y][x] within the character
+    const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+    let (left, top, right, bottom) = viewport.clip(grid, 2, 4);
+    let mut out = String::new();
+    for y in (top..bottom).step_by(4) {
+        for x in (left..right).step_by(2) {
+            let mut bits = 0;
+            for (dy, row) in DOTS.iter().enumerate() {
+                for (dx, bit) in row.iter().enumerate() {
+                    if x + dx < right && cell_is_set(grid, x + dx, y + dy, &is_set) {
+                        bits |= bit;
+                    }
+                }
+            }
+            out.push(char::from_u32(0x2800 + bits).expect("braille block is valid unicode"));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Draw the grid with each character covering 1x2 cells using half block characters
+pub fn render_half_blocks<T>(
+    grid: &Grid<T>,
+    viewport: &Viewport,
+    is_set: impl Fn(&T) -> bool,
+) -> String {
+    let (left, top, right, bottom) = viewport.clip(grid, 1, 2);
+    let mut out = String::new();
+    for y in (top..bottom).step_by(2) {
+        for x in left..right {
+            out.push(
+                match (
+                    cell_is_set(grid, x, y, &is_set),
+                    cell_is_set(grid, x, y + 1, &is_set),
+                ) {
+                    (false, false) => ' ',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (true, true) => '█',
+                },
+            );
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Draw the grid in half blocks using 24-bit ANSI colours, with the upper cell
+/// as the foreground colour and the lower cell as the background
+pub fn render_half_blocks_coloured<T>(
+    grid: &Grid<T>,
+    viewport: &Viewport,
+    colour: impl Fn(&T) -> Colour,
+) -> String {
+    let (left, top, right, bottom) = viewport.clip(grid, 1, 2);
+    let mut out = String::new();
+    for y in (top..bottom).step_by(2) {
+        for x in left..right {
+            let upper = grid.get(x, y).map(&colour).unwrap_or(Colour::BLACK);
+            let lower = grid.get(x, y + 1).map(&colour).unwrap_or(Colour::BLACK);
+            // writing to a String can't fail
+            let _ = write!(
+                out,
+                "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m▀",
+                upper.r, upper.g, upper.b, lower.r, lower.g, lower.b
+            );
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+impl Display {
+    #[cfg(test)]
+    pub fn to_braille(&self, viewport: &Viewport) -> String {
+        render_braille(self.grid(), viewport, |p| *p)
+    }
+
+    pub fn to_half_blocks(&self, viewport: &Viewport) -> String {
+        render_half_blocks(self.grid(), viewport, |p| *p)
+    }
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn test_render_braille() {
+    let display = Display::from_str(
+        "#..#
+.#..
+..#.
+...#
+#...",
+    )
+    .unwrap();
+    let viewport = Viewport::new(0, 0, 80, None);
+    assert_eq!(display.to_braille(&viewport), "⠑⢌\n⠁⠀\n");
+}
+
+#[test]
+fn test_render_half_blocks() {
+    let display = Display::from_str(
+        "#.#
+##.
+.#.",
+    )
+    .unwrap();
+    let viewport = Viewport::new(0, 0, 80, None);
+    assert_eq!(display.to_half_blocks(&viewport), "█▄▀\n ▀ \n");
+}
+
+#[test]
+fn test_viewport_clipping() {
+    let display = Display::from_str(
+        "#.#.#.
+.#.#.#",
+    )
+    .unwrap();
+    assert_eq!(
+        display.to_half_blocks(&Viewport::new(1, 0, 3, None)),
+        "▄▀▄\n"
+    );
+    assert_eq!(
+        display.to_half_blocks(&Viewport::new(0, 1, 80, Some(1))),
+        " ▀ ▀ ▀\n"
+    );
+}
+
+#[test]
+fn test_render_coloured() {
+    let mut grid = Grid::new(1, 2);
+    grid.set(0, 0, 1u8).unwrap();
+    let rendered = render_half_blocks_coloured(&grid, &Viewport::new(0, 0, 80, None), |v| {
+        Colour::grey(*v * 255)
+    });
+    assert_eq!(rendered, "\x1b[38;2;255;255;255m\x1b[48;2;0;0;0m▀\x1b[0m\n");
+}
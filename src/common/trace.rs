@@ -0,0 +1,292 @@
+use super::cpu::{Cpu, Snapshot, State};
+use std::{error::Error, fmt};
+
+/// How far through its instruction a cycle was
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// The first cycle of an instruction which takes more than one
+    Begin,
+    /// Neither the first nor the last cycle of an instruction
+    Mid,
+    /// The last cycle of an instruction, when it takes effect. Single cycle
+    /// instructions only ever complete.
+    Complete,
+}
+
+impl Phase {
+    /// The phase of a cycle which the Cpu started in one state and finished in another.
+    /// An instruction has finished once the Cpu is ready for the next, even if a jump
+    /// took it back to the same place.
+    fn of_cycle(before: State, after: State) -> Self {
+        match (before, after) {
+            (_, State::BeginNextInstruction | State::Complete) => Phase::Complete,
+            (State::BeginNextInstruction, _) => Phase::Begin,
+            _ => Phase::Mid,
+        }
+    }
+}
+
+/// What happened during one cycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub cycle: usize,
+    pub pc: usize,
+    /// The instruction as it would be written in source
+    pub instruction: String,
+    pub phase: Phase,
+    /// The registers while the cycle was running
+    pub registers_during: Vec<i32>,
+    /// The registers once the cycle had finished
+    pub registers_after: Vec<i32>,
+}
+
+impl TraceEntry {
+    /// X, the first register, while the cycle was running
+    pub fn x_during(&self) -> i32 {
+        self.registers_during[0]
+    }
+
+    /// The old and new values of X, if this cycle changed it
+    pub fn x_change(&self) -> Option<(i32, i32)> {
+        self.changes()
+            .find(|(r, _, _)| *r == 0)
+            .map(|(_, from, to)| (from, to))
+    }
+
+    /// Each register this cycle changed, with its old and new values
+    pub fn changes(&self) -> impl Iterator<Item = (usize, i32, i32)> + '_ {
+        self.registers_during
+            .iter()
+            .zip(self.registers_after.iter())
+            .enumerate()
+            .filter(|(_, (from, to))| from != to)
+            .map(|(r, (from, to))| (r, *from, *to))
+    }
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>5} pc={:<4} {:<10} {:<8} x={}",
+            self.cycle,
+            self.pc,
+            self.instruction,
+            // Debug ignores the width, so format it first to pad it
+            format!("{:?}", self.phase),
+            self.x_during()
+        )?;
+        for (r, from, to) in self.changes() {
+            write!(f, " r{}: {} -> {}", r, from, to)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum TraceError {
+    /// The program was still running after this many cycles
+    CycleLimit(usize),
+}
+
+impl Error for TraceError {}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::CycleLimit(max_cycles) => write!(
+                f,
+                "The program was still running after {} cycles",
+                max_cycles
+            ),
+        }
+    }
+}
+
+/// Every cycle of a run of a program, along with a snapshot of the Cpu from before
+/// each cycle so it can be put back to any point
+pub struct Trace {
+    entries: Vec<TraceEntry>,
+    snapshots: Vec<Snapshot>,
+}
+
+impl Trace {
+    /// Run the Cpu until its program is complete, recording each cycle. Programs can
+    /// jump, so give up rather than record forever if it takes more than max_cycles.
+    pub fn record(cpu: &mut Cpu, max_cycles: usize) -> Result<Self, TraceError> {
+        let mut entries = Vec::new();
+        let mut snapshots = Vec::new();
+        while !cpu.is_complete() {
+            if entries.len() == max_cycles {
+                return Err(TraceError::CycleLimit(max_cycles));
+            }
+            let before = cpu.snapshot();
+            let instruction = cpu
+                .disassemble_current()
+                .expect("an incomplete cpu has an instruction to run");
+            cpu.cycle();
+            let phase = Phase::of_cycle(before.state(), cpu.state());
+            entries.push(TraceEntry {
+                cycle: before.cycle(),
+                pc: before.pc(),
+                instruction,
+                phase,
+                registers_during: before.registers().to_vec(),
+                registers_after: cpu.registers().to_vec(),
+            });
+            snapshots.push(before);
+        }
+        // so the end of the run can be gone back to as well
+        snapshots.push(cpu.snapshot());
+        Ok(Self { entries, snapshots })
+    }
+
+    #[cfg(test)]
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    /// What happened during the given cycle, counting the same way as the Cpu
+    pub fn entry(&self, cycle: usize) -> Option<&TraceEntry> {
+        self.entries.get(cycle.checked_sub(self.first_cycle())?)
+    }
+
+    /// The value of X during every cycle, as returned by Cpu::run_to_completion
+    #[cfg(test)]
+    pub fn x_values(&self) -> Vec<i32> {
+        self.entries.iter().map(|e| e.x_during()).collect()
+    }
+
+    /// The cycle which last changed X before the given cycle ran, so is responsible for
+    /// the value X had during it. None if X still had its starting value.
+    pub fn last_change_before(&self, cycle: usize) -> Option<&TraceEntry> {
+        self.entries
+            .iter()
+            .take(cycle.saturating_sub(self.first_cycle()))
+            .rev()
+            .find(|e| e.x_change().is_some())
+    }
+
+    /// Put the Cpu back to how it was just before the given cycle ran. Cycles past the
+    /// end of the program put it back to the end, and cycles before the recording
+    /// started put it back to the start.
+    pub fn rewind(&self, cpu: &mut Cpu, cycle: usize) {
+        let index = cycle
+            .saturating_sub(self.first_cycle())
+            .min(self.snapshots.len() - 1);
+        cpu.restore(&self.snapshots[index]);
+    }
+
+    /// The Cpu's cycle count when the recording started, which is 1 unless it had
+    /// already been run for a while
+    fn first_cycle(&self) -> usize {
+        self.snapshots[0].cycle()
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+const PROGRAM: &str = "noop
+addx 3
+addx -5";
+
+#[test]
+fn test_record_phases() {
+    let mut cpu = Cpu::compile(PROGRAM).unwrap();
+    let trace = Trace::record(&mut cpu, 1000).unwrap();
+    let phases = trace.entries().iter().map(|e| e.phase).collect::<Vec<_>>();
+    assert_eq!(
+        phases,
+        vec![
+            Phase::Complete,
+            Phase::Begin,
+            Phase::Complete,
+            Phase::Begin,
+            Phase::Complete
+        ]
+    );
+    assert_eq!(trace.x_values(), vec![1, 1, 1, 4, 4]);
+    assert_eq!(trace.entry(3).unwrap().x_change(), Some((1, 4)));
+    assert_eq!(trace.entry(4).unwrap().x_change(), None);
+    assert_eq!(trace.entry(0), None);
+}
+
+#[test]
+fn test_jump_to_self_completes() {
+    // this never finishes, so step through by hand rather than recording
+    let isa = "registers: x\nwait t: cycles 3, jump $0".parse().unwrap();
+    let mut cpu = Cpu::compile_for(isa, "wait 0").unwrap();
+    let mut phases = Vec::new();
+    for _ in 0..4 {
+        let before = cpu.state();
+        cpu.cycle();
+        phases.push(Phase::of_cycle(before, cpu.state()));
+        assert_eq!(cpu.pc(), 0);
+    }
+    assert_eq!(
+        phases,
+        vec![Phase::Begin, Phase::Mid, Phase::Complete, Phase::Begin]
+    );
+}
+
+#[test]
+fn test_last_change_before() {
+    let mut cpu = Cpu::compile(PROGRAM).unwrap();
+    let trace = Trace::record(&mut cpu, 1000).unwrap();
+    assert_eq!(trace.last_change_before(3), None);
+    let change = trace.last_change_before(4).unwrap();
+    assert_eq!((change.cycle, change.instruction.as_str()), (3, "addx 3"));
+}
+
+#[test]
+fn test_rewind_and_replay() {
+    let input = include_str!("../inputs/day10-sample.txt");
+    let mut cpu = Cpu::compile(input).unwrap();
+    let trace = Trace::record(&mut cpu, 1000).unwrap();
+    assert_eq!(trace.entry(220).unwrap().x_during(), 18);
+
+    trace.rewind(&mut cpu, 220);
+    assert_eq!(cpu.current_cycle(), 220);
+    assert_eq!(cpu.cycle(), 18);
+
+    // replaying from a rewind gives the same values as the first time round
+    trace.rewind(&mut cpu, 100);
+    let replayed = cpu.run_to_completion();
+    assert_eq!(replayed, trace.x_values()[99..]);
+}
+
+#[test]
+fn test_record_gives_up_on_a_loop() {
+    let isa = "registers: x\nwait t: cycles 3, jump $0".parse().unwrap();
+    let mut cpu = Cpu::compile_for(isa, "wait 0").unwrap();
+    assert!(matches!(
+        Trace::record(&mut cpu, 100),
+        Err(TraceError::CycleLimit(100))
+    ));
+    assert_eq!(cpu.current_cycle(), 101);
+}
+
+#[test]
+fn test_record_part_way_through() {
+    let mut cpu = Cpu::compile(PROGRAM).unwrap();
+    cpu.cycle();
+    cpu.cycle();
+    let trace = Trace::record(&mut cpu, 1000).unwrap();
+    assert_eq!(trace.entry(2), None);
+    assert_eq!(trace.entry(3).unwrap().x_change(), Some((1, 4)));
+    assert_eq!(trace.x_values(), vec![1, 4, 4]);
+    assert_eq!(trace.last_change_before(4).unwrap().cycle, 3);
+
+    trace.rewind(&mut cpu, 1);
+    assert_eq!(cpu.current_cycle(), 3);
+    trace.rewind(&mut cpu, 4);
+    assert_eq!((cpu.current_cycle(), cpu.get_x()), (4, 4));
+}
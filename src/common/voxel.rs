@@ -0,0 +1,454 @@
+#[cfg(test)]
+use super::grid::Grid;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt::Display,
+    iter,
+    ops::{Add, Sub},
+};
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize,
+)]
+pub struct Point3 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Point3 {
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+
+    #[cfg(test)]
+    pub fn manhattan_distance(&self, other: &Point3) -> u32 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y) + self.z.abs_diff(other.z)
+    }
+
+    pub fn neighbours(&self, kind: Neighbourhood3) -> impl Iterator<Item = Point3> + '_ {
+        kind.offsets().map(move |o| *self + o)
+    }
+
+    /// True if the two points are distinct unit cubes sharing a face
+    #[cfg(test)]
+    pub fn shares_face_with(&self, other: &Point3) -> bool {
+        self.manhattan_distance(other) == 1
+    }
+
+    pub fn component_min(self, other: Self) -> Self {
+        Point3::new(
+            self.x.min(other.x),
+            self.y.min(other.y),
+            self.z.min(other.z),
+        )
+    }
+
+    pub fn component_max(self, other: Self) -> Self {
+        Point3::new(
+            self.x.max(other.x),
+            self.y.max(other.y),
+            self.z.max(other.z),
+        )
+    }
+}
+
+impl Add for Point3 {
+    type Output = Point3;
+
+    fn add(self, rhs: Point3) -> Self::Output {
+        Point3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Point3;
+
+    fn sub(self, rhs: Point3) -> Self::Output {
+        Point3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Add<(i32, i32, i32)> for Point3 {
+    type Output = Point3;
+
+    fn add(self, (x, y, z): (i32, i32, i32)) -> Self::Output {
+        self + Point3::new(x, y, z)
+    }
+}
+
+/// Which of the 26 surrounding cubes count as neighbours
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbourhood3 {
+    /// The 6 cubes sharing a face
+    Faces,
+    /// The 18 cubes sharing a face or an edge
+    #[cfg(test)]
+    Edges,
+    /// All 26 cubes sharing a face, edge or corner
+    #[cfg(test)]
+    Corners,
+}
+
+impl Neighbourhood3 {
+    pub fn offsets(&self) -> impl Iterator<Item = Point3> {
+        // the number of axes an offset moves along is 1 for a face, 2 for an edge, 3 for a corner
+        let max_axes = match self {
+            Neighbourhood3::Faces => 1,
+            #[cfg(test)]
+            Neighbourhood3::Edges => 2,
+            #[cfg(test)]
+            Neighbourhood3::Corners => 3,
+        };
+        (-1..=1)
+            .flat_map(|z| (-1..=1).flat_map(move |y| (-1..=1).map(move |x| Point3::new(x, y, z))))
+            .filter(move |o| {
+                let axes = [o.x, o.y, o.z].iter().filter(|v| **v != 0).count();
+                axes >= 1 && axes <= max_axes
+            })
+    }
+}
+
+/// An inclusive box of points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl BoundingBox {
+    pub fn new(min: Point3, max: Point3) -> Self {
+        Self { min, max }
+    }
+
+    /// The smallest box containing every point, or None if there aren't any
+    pub fn around<'a>(points: impl IntoIterator<Item = &'a Point3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = *points.next()?;
+        Some(points.fold(BoundingBox::new(first, first), |b, p| {
+            BoundingBox::new(b.min.component_min(*p), b.max.component_max(*p))
+        }))
+    }
+
+    /// Grow the box by n in every direction
+    pub fn expand(&self, n: i32) -> Self {
+        BoundingBox::new(self.min + (-n, -n, -n), self.max + (n, n, n))
+    }
+
+    pub fn contains(&self, p: &Point3) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x)
+            && (self.min.y..=self.max.y).contains(&p.y)
+            && (self.min.z..=self.max.z).contains(&p.z)
+    }
+
+    /// True if the point is inside the box on one of its outer faces
+    pub fn on_surface(&self, p: &Point3) -> bool {
+        self.contains(p)
+            && (p.x == self.min.x
+                || p.x == self.max.x
+                || p.y == self.min.y
+                || p.y == self.max.y
+                || p.z == self.min.z
+                || p.z == self.max.z)
+    }
+
+    /// Size along each axis
+    pub fn dimensions(&self) -> (usize, usize, usize) {
+        (
+            (self.max.x - self.min.x + 1).max(0) as usize,
+            (self.max.y - self.min.y + 1).max(0) as usize,
+            (self.max.z - self.min.z + 1).max(0) as usize,
+        )
+    }
+
+    pub fn volume(&self) -> usize {
+        let (w, h, d) = self.dimensions();
+        w * h * d
+    }
+
+    /// Every point in the box, in z, then y, then x order
+    pub fn points(&self) -> impl Iterator<Item = Point3> {
+        let BoundingBox { min, max } = *self;
+        (min.z..=max.z).flat_map(move |z| {
+            (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Point3::new(x, y, z)))
+        })
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Debug)]
+pub enum VoxelOperationError {
+    OutOfBounds(Point3, BoundingBox),
+}
+
+impl Error for VoxelOperationError {}
+
+impl Display for VoxelOperationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VoxelOperationError::OutOfBounds(p, b) => write!(
+                f,
+                "The given point {},{},{} is not inside the box from {},{},{} to {},{},{}",
+                p.x, p.y, p.z, b.min.x, b.min.y, b.min.z, b.max.x, b.max.y, b.max.z
+            ),
+        }
+    }
+}
+
+/// A dense 3D grid covering a bounding box, which need not start at the origin
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "Grid3Data<T>", bound(deserialize = "T: Deserialize<'de>"))]
+pub struct Grid3<T> {
+    content: Vec<T>,
+    bounds: BoundingBox,
+}
+
+impl<T> Grid3<T>
+where
+    T: Default + Clone,
+{
+    pub fn new(bounds: BoundingBox) -> Self {
+        Self {
+            content: iter::repeat_n(T::default(), bounds.volume()).collect(),
+            bounds,
+        }
+    }
+
+    /// A 2D layer of the grid at the given position along an axis. Slicing along Z
+    /// gives a grid of x by y, along Y gives x by z, and along X gives y by z.
+    #[cfg(test)]
+    pub fn slice(&self, axis: Axis, at: i32) -> Result<Grid<T>, VoxelOperationError> {
+        let (min, max) = (self.bounds.min, self.bounds.max);
+        let (w, h, d) = self.bounds.dimensions();
+        let (width, height, range) = match axis {
+            Axis::Z => (w, h, min.z..=max.z),
+            Axis::Y => (w, d, min.y..=max.y),
+            Axis::X => (h, d, min.x..=max.x),
+        };
+        if !range.contains(&at) {
+            let p = match axis {
+                Axis::Z => Point3::new(min.x, min.y, at),
+                Axis::Y => Point3::new(min.x, at, min.z),
+                Axis::X => Point3::new(at, min.y, min.z),
+            };
+            return Err(VoxelOperationError::OutOfBounds(p, self.bounds));
+        }
+
+        let mut layer = Grid::new(width, height);
+        for (u, v) in layer.iter_coords().collect::<Vec<_>>() {
+            let (du, dv) = (u as i32, v as i32);
+            let p = match axis {
+                Axis::Z => Point3::new(min.x + du, min.y + dv, at),
+                Axis::Y => Point3::new(min.x + du, at, min.z + dv),
+                Axis::X => Point3::new(at, min.y + du, min.z + dv),
+            };
+            layer
+                .set(u, v, self.get(&p)?.clone())
+                .expect("layer is sized to fit the box");
+        }
+        Ok(layer)
+    }
+}
+
+impl<T> Grid3<T> {
+    pub fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    pub fn get(&self, p: &Point3) -> Result<&T, VoxelOperationError> {
+        let index = self.index_of(p)?;
+        Ok(&self.content[index])
+    }
+
+    pub fn set(&mut self, p: &Point3, value: T) -> Result<(), VoxelOperationError> {
+        let index = self.index_of(p)?;
+        self.content[index] = value;
+        Ok(())
+    }
+
+    /// Neighbouring points which are inside the grid
+    #[cfg(test)]
+    pub fn neighbours(
+        &self,
+        p: &Point3,
+        kind: Neighbourhood3,
+    ) -> impl Iterator<Item = Point3> + '_ {
+        let p = *p;
+        kind.offsets()
+            .map(move |o| p + o)
+            .filter(|n| self.bounds.contains(n))
+    }
+
+    fn index_of(&self, p: &Point3) -> Result<usize, VoxelOperationError> {
+        if !self.bounds.contains(p) {
+            return Err(VoxelOperationError::OutOfBounds(*p, self.bounds));
+        }
+        let (w, h, _) = self.bounds.dimensions();
+        let o = *p - self.bounds.min;
+        Ok((o.z as usize * h + o.y as usize) * w + o.x as usize)
+    }
+}
+
+/// The fields of a Grid3 as they're stored, checked for consistency before becoming one
+#[derive(Deserialize)]
+struct Grid3Data<T> {
+    content: Vec<T>,
+    bounds: BoundingBox,
+}
+
+impl<T> TryFrom<Grid3Data<T>> for Grid3<T> {
+    type Error = String;
+
+    fn try_from(data: Grid3Data<T>) -> Result<Self, Self::Error> {
+        let BoundingBox { min, max } = data.bounds;
+        if min.x > max.x || min.y > max.y || min.z > max.z {
+            return Err(format!(
+                "The box from {},{},{} to {},{},{} is inside out",
+                min.x, min.y, min.z, max.x, max.y, max.z
+            ));
+        }
+        if data.content.len() != data.bounds.volume() {
+            return Err(format!(
+                "A box of {} cubes can't be filled with {} values",
+                data.bounds.volume(),
+                data.content.len()
+            ));
+        }
+        Ok(Grid3 {
+            content: data.content,
+            bounds: data.bounds,
+        })
+    }
+}
+
+/// A sparse set of unit cubes
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct VoxelSet {
+    points: HashSet<Point3>,
+}
+
+impl VoxelSet {
+    pub fn contains(&self, p: &Point3) -> bool {
+        self.points.contains(p)
+    }
+
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::around(self.points.iter())
+    }
+
+    /// The neighbours of p which are in the set
+    pub fn neighbours_in_set<'a>(
+        &'a self,
+        p: &'a Point3,
+        kind: Neighbourhood3,
+    ) -> impl Iterator<Item = Point3> + 'a {
+        p.neighbours(kind).filter(|n| self.contains(n))
+    }
+
+    /// The number of faces where two cubes of the set touch, each counted once
+    pub fn shared_faces(&self) -> usize {
+        self.points
+            .iter()
+            .map(|p| self.neighbours_in_set(p, Neighbourhood3::Faces).count())
+            .sum::<usize>()
+            / 2
+    }
+
+    /// The number of cube faces which don't touch another cube in the set
+    pub fn surface_area(&self) -> usize {
+        self.points.len() * 6 - self.shared_faces() * 2
+    }
+
+    /// Every empty position sharing a face with a cube in the set, once per shared face
+    pub fn exposed_faces(&self) -> impl Iterator<Item = Point3> + '_ {
+        self.points.iter().flat_map(|p| {
+            p.neighbours(Neighbourhood3::Faces)
+                .filter(|n| !self.contains(n))
+                .collect::<Vec<_>>()
+        })
+    }
+
+    /// Fill a dense grid with the set, covering its bounding box
+    pub fn to_grid(&self) -> Option<Grid3<bool>> {
+        let mut grid = Grid3::new(self.bounding_box()?);
+        for p in self.points.iter() {
+            grid.set(p, true)
+                .expect("the grid covers the bounding box of the set");
+        }
+        Some(grid)
+    }
+}
+
+impl FromIterator<Point3> for VoxelSet {
+    fn from_iter<I: IntoIterator<Item = Point3>>(iter: I) -> Self {
+        Self {
+            points: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[test]
+fn test_neighbourhood_sizes() {
+    assert_eq!(Neighbourhood3::Faces.offsets().count(), 6);
+    assert_eq!(Neighbourhood3::Edges.offsets().count(), 18);
+    assert_eq!(Neighbourhood3::Corners.offsets().count(), 26);
+    assert!(Neighbourhood3::Faces
+        .offsets()
+        .all(|o| o.shares_face_with(&Point3::default())));
+}
+
+#[test]
+fn test_bounding_box() {
+    let points = [Point3::new(1, -2, 3), Point3::new(-1, 4, 0)];
+    let b = BoundingBox::around(points.iter()).unwrap();
+    assert_eq!(b.min, Point3::new(-1, -2, 0));
+    assert_eq!(b.max, Point3::new(1, 4, 3));
+    assert_eq!(b.dimensions(), (3, 7, 4));
+    assert_eq!(b.points().count(), b.volume());
+    assert!(b.on_surface(&Point3::new(0, -2, 1)));
+    assert!(!b.on_surface(&Point3::new(0, 0, 1)));
+    assert!(!b.expand(1).on_surface(&Point3::new(0, -2, 1)));
+}
+
+#[test]
+fn test_grid3_and_slices() {
+    let mut grid = Grid3::new(BoundingBox::new(
+        Point3::new(-1, 0, 5),
+        Point3::new(1, 1, 6),
+    ));
+    grid.set(&Point3::new(1, 0, 6), 7u8).unwrap();
+    assert_eq!(*grid.get(&Point3::new(1, 0, 6)).unwrap(), 7);
+    assert!(grid.get(&Point3::new(2, 0, 6)).is_err());
+
+    let z = grid.slice(Axis::Z, 6).unwrap();
+    assert_eq!((z.width(), z.height()), (3, 2));
+    assert_eq!(*z.get(2, 0).unwrap(), 7);
+    let x = grid.slice(Axis::X, 1).unwrap();
+    assert_eq!((x.width(), x.height()), (2, 2));
+    assert_eq!(*x.get(0, 1).unwrap(), 7);
+    assert!(grid.slice(Axis::Y, 3).is_err());
+
+    let corner = grid.neighbours(&Point3::new(-1, 0, 5), Neighbourhood3::Corners);
+    assert_eq!(corner.count(), 7);
+}
+
+#[test]
+fn test_voxel_set_faces() {
+    let set: VoxelSet = [Point3::new(1, 1, 1), Point3::new(2, 1, 1)]
+        .into_iter()
+        .collect();
+    assert_eq!(set.shared_faces(), 1);
+    assert_eq!(set.surface_area(), 10);
+    assert_eq!(set.exposed_faces().count(), 10);
+    let grid = set.to_grid().unwrap();
+    assert_eq!(grid.bounds().dimensions(), (2, 1, 1));
+}
@@ -1,8 +1,11 @@
-use std::error::Error;
+use std::{cell::RefCell, error::Error, rc::Rc};
 
 use crate::common::cpu::Cpu;
 use crate::common::day;
 use crate::common::display::Display;
+use crate::common::image;
+use crate::common::peripheral::{Crt, SignalSampler};
+use crate::common::terminal::{self, Viewport};
 
 pub struct Day10 {
     input: &'static str,
@@ -20,55 +23,49 @@ impl day::Day for Day10 {
     fn run(&mut self) -> day::Result {
         let part1 = run_part1(self.input)?;
         let part2 = run_part2(self.input)?;
+        if let Some(path) = image::output_path("day10-crt.png") {
+            part2.save_image(path, 8)?;
+        }
+        if terminal::wants_drawing() {
+            print!("{}", part2.to_half_blocks(&Viewport::terminal()));
+        }
+        let letters = part2.recognise_text();
+        if !letters.is_complete() {
+            log::warn!(
+                "Couldn't recognise glyphs starting at x = {:?}",
+                letters.unrecognised
+            );
+        }
         Ok((
             Some(format!("Signal strength is {}", part1)),
-            Some(format!("\n{}", part2)),
+            Some(format!("{}\n\n{}", letters.text, part2)),
         ))
     }
 }
 
-fn run_part1(input: &str) -> Result<i32, Box<dyn Error>> {
-    let mut cpu = Cpu::compile(input)?;
-    let mut result = 0;
-    let x = run_cycles(&mut cpu, 20); // 20
-    result += x * 20;
-    let x = run_cycles(&mut cpu, 40); // 60
-    result += x * 60;
-    let x = run_cycles(&mut cpu, 40); // 100
-    result += x * 100;
-    let x = run_cycles(&mut cpu, 40); // 140
-    result += x * 140;
-    let x = run_cycles(&mut cpu, 40); // 180
-    result += x * 180;
-    let x = run_cycles(&mut cpu, 40); // 220
-    result += x * 220;
-    Ok(result)
+/// The cycles at which the signal strength is measured
+fn sampler() -> SignalSampler {
+    SignalSampler::every(20, 40, 220).expect("the step isn't zero")
 }
 
-fn run_cycles(cpu: &mut Cpu, cycles: u32) -> i32 {
-    let mut x = cpu.get_x();
-    for _ in 0..cycles {
-        x = cpu.cycle();
-    }
-    x
+fn run_part1(input: &str) -> Result<i32, Box<dyn Error>> {
+    let mut cpu = Cpu::compile(input)?;
+    let sampler = Rc::new(RefCell::new(sampler()));
+    cpu.attach(sampler.clone());
+    cpu.run_to_completion();
+    let strength = sampler.borrow().strength();
+    Ok(strength)
 }
 
-fn run_part2(input: &str) -> Result<String, Box<dyn Error>> {
+fn run_part2(input: &str) -> Result<Display, Box<dyn Error>> {
     let mut cpu = Cpu::compile(input)?;
-    let mut display = Display::new(40, 6);
-    let values = cpu.run_to_completion();
-    for (beam_position, value) in values.into_iter().enumerate() {
-        let x = beam_position % 40;
-        let y = beam_position / 40;
-        if y >= 6 || x >= 40 {
-            println!("Terminating early");
-            break;
-        }
-        let pixel = x as i32 == value || x as i32 + 1 == value || x as i32 == value + 1;
-        display.set(x, y, pixel)?;
-        println!("Setting {},{} to {}", x, y, pixel);
-    }
-    Ok(display.to_string())
+    let crt = Rc::new(RefCell::new(
+        Crt::new(40, 6).expect("the screen has a width"),
+    ));
+    cpu.attach(crt.clone());
+    cpu.run_to_completion();
+    let display = crt.borrow().display().clone();
+    Ok(display)
 }
 
 #[test]
@@ -82,30 +79,22 @@ fn test_part1_sample() {
 fn test_part1_sample_stages() {
     let input = include_str!("inputs/day10-sample.txt");
     let mut cpu = Cpu::compile(input).expect("This should compile");
-    let x = run_cycles(&mut cpu, 20); // 20
-    let strength = x * 20;
-    assert_eq!(strength, 420);
-    let x = run_cycles(&mut cpu, 40); // 60
-    let strength = x * 60;
-    assert_eq!(strength, 1140);
-    let x = run_cycles(&mut cpu, 40); // 100
-    let strength = x * 100;
-    assert_eq!(strength, 1800);
-    let x = run_cycles(&mut cpu, 40); // 140
-    let strength = x * 140;
-    assert_eq!(strength, 2940);
-    let x = run_cycles(&mut cpu, 40); // 180
-    let strength = x * 180;
-    assert_eq!(strength, 2880);
-    let x = run_cycles(&mut cpu, 40); // 220
-    let strength = x * 220;
-    assert_eq!(strength, 3960);
+    let sampler = Rc::new(RefCell::new(sampler()));
+    cpu.attach(sampler.clone());
+    cpu.run_to_completion();
+    let strengths = sampler
+        .borrow()
+        .samples()
+        .iter()
+        .map(|(cycle, x)| *cycle as i32 * x)
+        .collect::<Vec<_>>();
+    assert_eq!(strengths, vec![420, 1140, 1800, 2940, 2880, 3960]);
 }
 
 #[test]
 fn test_part2_sample() {
     let input = include_str!("inputs/day10-sample.txt");
-    let output = run_part2(input).expect("Should not explode");
+    let output = run_part2(input).expect("Should not explode").to_string();
     let expected = "##..##..##..##..##..##..##..##..##..##..
 ###...###...###...###...###...###...###.
 ####....####....####....####....####....
@@ -1,19 +1,28 @@
+mod expression;
+
+use self::expression::Expression;
 use crate::common::day;
+use itertools::Itertools;
 use std::collections::HashMap;
-pub struct Day11 {}
+
+pub struct Day11 {
+    input: &'static str,
+}
 
 impl Day11 {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            input: include_str!("inputs/day11.txt"),
+        }
     }
 }
 
 impl day::Day for Day11 {
     fn run(&mut self) -> day::Result {
-        let mut monkeys = input();
+        let mut monkeys = parse_input(self.input)?;
         run_rounds(&mut monkeys, 20, false);
         let part1_result = monkey_business(&monkeys);
-        let mut monkeys = input();
+        let mut monkeys = parse_input(self.input)?;
         run_rounds(&mut monkeys, 10000, true);
         let part2_result = monkey_business(&monkeys);
         Ok((
@@ -25,7 +34,7 @@ impl day::Day for Day11 {
 
 struct Monkey {
     items: Vec<u64>,
-    operation: Box<dyn Fn(u64) -> u64>,
+    operation: Expression,
     test: u64,
     if_true: usize,
     if_false: usize,
@@ -35,7 +44,7 @@ struct Monkey {
 impl Monkey {
     fn new(
         items: Vec<u64>,
-        operation: Box<dyn Fn(u64) -> u64>,
+        operation: Expression,
         test: u64,
         if_true: usize,
         if_false: usize,
@@ -51,11 +60,11 @@ impl Monkey {
     }
 
     fn operation(&self, w: u64) -> u64 {
-        (self.operation)(w)
+        self.operation.evaluate(w)
     }
 
     fn test(&self, w: u64) -> bool {
-        w % self.test == 0
+        w.is_multiple_of(self.test)
     }
 
     fn add_item(&mut self, w: u64) {
@@ -148,49 +157,90 @@ fn monkey_business(monkeys: &HashMap<usize, Monkey>) -> usize {
         .product()
 }
 
-macro_rules! monkey {
-    ($items:expr, $observe:expr, $divisor:expr, $if_true:expr, $if_false: expr) => {
-        Monkey::new($items, Box::new($observe), $divisor, $if_true, $if_false)
-    };
+/// Reads the notes on every monkey, separated by blank lines
+fn parse_input(input: &str) -> Result<HashMap<usize, Monkey>, String> {
+    let mut monkeys = HashMap::new();
+    for notes in input.split("\n\n") {
+        let (id, monkey) = parse_monkey(notes)?;
+        if monkeys.insert(id, monkey).is_some() {
+            return Err(format!("There are notes on monkey {} more than once", id));
+        }
+    }
+    for id in monkeys.keys().copied().sorted() {
+        let monkey = &monkeys[&id];
+        for target in [monkey.if_true, monkey.if_false] {
+            if target == id || !monkeys.contains_key(&target) {
+                return Err(format!("Monkey {} can't throw to monkey {}", id, target));
+            }
+        }
+    }
+    Ok(monkeys)
 }
 
-#[cfg(not(test))]
-fn input() -> HashMap<usize, Monkey> {
-    HashMap::from([
-        (
-            0,
-            monkey!(vec![89, 95, 92, 64, 87, 68], |old| old * 11, 2, 7, 4),
-        ),
-        (1, monkey!(vec![87, 67], |old| old + 1, 13, 3, 6)),
-        (2, monkey!(vec![95, 79, 92, 82, 60], |old| old + 6, 3, 1, 6)),
-        (3, monkey!(vec![67, 97, 56], |old| old * old, 17, 7, 0)),
-        (
-            4,
-            monkey!(
-                vec![80, 68, 87, 94, 61, 59, 50, 68],
-                |old| old * 7,
-                19,
-                5,
-                2
-            ),
-        ),
-        (5, monkey!(vec![73, 51, 76, 59], |old| old + 8, 7, 2, 1)),
-        (6, monkey!(vec![92], |old| old + 5, 11, 3, 0)),
-        (
-            7,
-            monkey!(vec![99, 76, 78, 76, 79, 90, 89], |old| old + 7, 5, 4, 5),
-        ),
-    ])
+/// Reads the notes on one monkey, such as
+/// ```text
+/// Monkey 0:
+///   Starting items: 79, 98
+///   Operation: new = old * 19
+///   Test: divisible by 23
+///     If true: throw to monkey 2
+///     If false: throw to monkey 3
+/// ```
+fn parse_monkey(notes: &str) -> Result<(usize, Monkey), String> {
+    let mut lines = notes.lines().map(str::trim);
+    let mut field = |prefix: &str| {
+        let line = lines.next().unwrap_or_default();
+        line.strip_prefix(prefix)
+            .ok_or_else(|| format!("Expected '{}' but found '{}'", prefix, line))
+    };
+    let number = |s: &str| {
+        let s = s.trim();
+        s.parse().map_err(|_| format!("'{}' is not a number", s))
+    };
+
+    let id = field("Monkey ")?
+        .strip_suffix(':')
+        .ok_or("Expected ':' after the monkey's number")?;
+    let id = number(id)?;
+    let in_monkey = |e: String| format!("Monkey {}: {}", id, e);
+
+    let items = field("Starting items:")
+        .map_err(in_monkey)?
+        .split(',')
+        .filter(|s| !s.trim().is_empty())
+        .map(number)
+        .collect::<Result<Vec<u64>, _>>()
+        .map_err(in_monkey)?;
+    let operation = field("Operation: new =")
+        .map_err(in_monkey)?
+        .parse()
+        .map_err(in_monkey)?;
+    let test = field("Test: divisible by")
+        .and_then(number)
+        .map_err(in_monkey)?;
+    if test == 0 {
+        return Err(in_monkey("Can't test for divisibility by 0".to_owned()));
+    }
+    let if_true = field("If true: throw to monkey")
+        .and_then(number)
+        .map_err(in_monkey)?;
+    let if_false = field("If false: throw to monkey")
+        .and_then(number)
+        .map_err(in_monkey)?;
+    let rest = field("").map_err(in_monkey)?;
+    if !rest.is_empty() {
+        return Err(in_monkey(format!("Unexpected '{}' after the notes", rest)));
+    }
+
+    Ok((
+        id as usize,
+        Monkey::new(items, operation, test, if_true as usize, if_false as usize),
+    ))
 }
 
 #[cfg(test)]
 fn input() -> HashMap<usize, Monkey> {
-    HashMap::from([
-        (0, monkey!(vec![79, 98], |old| old * 19, 23, 2, 3)),
-        (1, monkey!(vec![54, 65, 75, 74], |old| old + 6, 19, 2, 0)),
-        (2, monkey!(vec![79, 60, 97], |old| old * old, 13, 1, 3)),
-        (3, monkey!(vec![74], |old| old + 3, 17, 0, 1)),
-    ])
+    parse_input(include_str!("inputs/day11-sample.txt")).expect("The sample should parse")
 }
 
 #[test]
@@ -284,3 +334,39 @@ fn test_part2_ten_thousand_rounds() {
     assert_eq!(monkeys[&2].times_inspected(), 1938);
     assert_eq!(monkeys[&3].times_inspected(), 52013);
 }
+
+#[test]
+fn test_parse_monkey() {
+    let (id, monkey) = parse_monkey(
+        "Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3",
+    )
+    .unwrap();
+    assert_eq!(id, 2);
+    assert_eq!(monkey.items, vec![79, 60, 97]);
+    assert_eq!(monkey.operation(4), 16);
+    assert_eq!((monkey.test, monkey.if_true, monkey.if_false), (13, 1, 3));
+}
+
+#[test]
+fn test_parse_errors() {
+    let error = |notes: &str| parse_monkey(notes).err().unwrap();
+    assert_eq!(
+        error("Monkey 1:\n  Starting items: 4, x"),
+        "Monkey 1: 'x' is not a number"
+    );
+    assert_eq!(
+        error("Monkey 1:\n  Starting items: 4\n  Test: divisible by 3"),
+        "Monkey 1: Expected 'Operation: new =' but found 'Test: divisible by 3'"
+    );
+
+    let input = include_str!("inputs/day11-sample.txt").replace("monkey 3", "monkey 7");
+    assert_eq!(
+        parse_input(&input).err().unwrap(),
+        "Monkey 0 can't throw to monkey 7"
+    );
+}
@@ -0,0 +1,118 @@
+use chumsky::prelude::*;
+use std::{fmt, str::FromStr};
+
+/// The right hand side of a monkey's operation, such as `old * 19`. Only addition and
+/// multiplication are allowed, since the worry management in part 2 relies on the result
+/// staying the same modulo every monkey's divisor.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Expression {
+    Old,
+    Number(u64),
+    Add(Box<Expression>, Box<Expression>),
+    Mul(Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    /// The new worry level given the old one
+    pub fn evaluate(&self, old: u64) -> u64 {
+        match self {
+            Expression::Old => old,
+            Expression::Number(n) => *n,
+            Expression::Add(l, r) => l.evaluate(old) + r.evaluate(old),
+            Expression::Mul(l, r) => l.evaluate(old) * r.evaluate(old),
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Old => write!(f, "old"),
+            Expression::Number(n) => write!(f, "{}", n),
+            Expression::Add(l, r) => write!(f, "{} + {}", l, r),
+            Expression::Mul(l, r) => {
+                // only a sum inside a product needs brackets to keep its meaning
+                let bracketed = |e: &Expression| match e {
+                    Expression::Add(_, _) => format!("({})", e),
+                    _ => e.to_string(),
+                };
+                write!(f, "{} * {}", bracketed(l), bracketed(r))
+            }
+        }
+    }
+}
+
+impl FromStr for Expression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parser().parse(s).map_err(|errs| {
+            errs.iter()
+                .map(|e| format!("{} at {:?}", e, e.span()))
+                .collect::<Vec<_>>()
+                .join("; ")
+        })
+    }
+}
+
+/// Parses `old`, whole numbers, `+`, `*` and brackets, with `*` binding more tightly
+pub fn parser() -> impl Parser<char, Expression, Error = Simple<char>> {
+    let expression = recursive(|expression| {
+        let number = text::int(10)
+            .try_map(|s: String, span| {
+                s.parse()
+                    .map_err(|_| Simple::custom(span, format!("{} is too big", s)))
+            })
+            .map(Expression::Number)
+            .labelled("number");
+
+        let atom = text::keyword("old")
+            .to(Expression::Old)
+            .or(number)
+            .or(expression.delimited_by(just('('), just(')')))
+            .padded();
+
+        let product = atom
+            .clone()
+            .then(just('*').ignore_then(atom).repeated())
+            .foldl(|l, r| Expression::Mul(Box::new(l), Box::new(r)));
+
+        product
+            .clone()
+            .then(just('+').ignore_then(product).repeated())
+            .foldl(|l, r| Expression::Add(Box::new(l), Box::new(r)))
+    });
+    expression.then_ignore(end())
+}
+
+#[test]
+fn test_simple_operations() {
+    let square: Expression = "old * old".parse().unwrap();
+    assert_eq!(
+        square,
+        Expression::Mul(Box::new(Expression::Old), Box::new(Expression::Old))
+    );
+    assert_eq!(square.evaluate(7), 49);
+    assert_eq!("old + 6".parse::<Expression>().unwrap().evaluate(7), 13);
+}
+
+#[test]
+fn test_precedence_and_brackets() {
+    let e: Expression = "old + 2 * old".parse().unwrap();
+    assert_eq!(e.evaluate(5), 15);
+    assert_eq!(e.to_string(), "old + 2 * old");
+
+    let e: Expression = "(old + 2) * old".parse().unwrap();
+    assert_eq!(e.evaluate(5), 35);
+    assert_eq!(e.to_string(), "(old + 2) * old");
+}
+
+#[test]
+fn test_invalid_expressions() {
+    assert!("old - 3".parse::<Expression>().is_err());
+    assert!("old *".parse::<Expression>().is_err());
+    assert!("new * 2".parse::<Expression>().is_err());
+    assert!("old * 99999999999999999999999"
+        .parse::<Expression>()
+        .is_err());
+}
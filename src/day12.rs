@@ -2,7 +2,12 @@ use std::{collections::HashMap, error::Error};
 
 use petgraph::{algo::dijkstra, stable_graph::NodeIndex, Graph};
 
-use crate::common::{day, grid::Grid};
+use crate::common::{
+    day,
+    grid::Grid,
+    image::{self, Colour},
+    terminal::{self, Viewport},
+};
 
 pub struct Day12 {
     input: &'static str,
@@ -102,6 +107,16 @@ fn find_shortest_path_between(graph: &Terrain, start: NodeIndex, end: NodeIndex)
 
 fn run_part1(input: &str) -> Result<usize, Box<dyn Error>> {
     let (grid, start, end) = parse_to_grid(input)?;
+    let colour = |h: &u8| Colour::grey(h * 9);
+    if let Some(path) = image::output_path("day12-heightmap.png") {
+        image::save(&grid, path, 4, colour)?;
+    }
+    if terminal::wants_drawing() {
+        print!(
+            "{}",
+            terminal::render_half_blocks_coloured(&grid, &Viewport::terminal(), colour)
+        );
+    }
     let (graph, node_indicies) = grid_to_graph(&grid);
     let start = node_indicies[&start];
     let end = node_indicies[&end];
@@ -17,10 +17,10 @@ impl Ord for Value {
         match (self, other) {
             (Value::List(l), Value::List(r)) => compare_lists(&mut l.iter(), &mut r.iter()),
             (Value::List(l), Value::Integer(r)) => {
-                compare_lists(&mut l.iter(), &mut vec![Value::Integer(*r)].iter())
+                compare_lists(&mut l.iter(), &mut [Value::Integer(*r)].iter())
             }
             (Value::Integer(l), Value::List(r)) => {
-                compare_lists(&mut vec![Value::Integer(*l)].iter(), &mut r.iter())
+                compare_lists(&mut [Value::Integer(*l)].iter(), &mut r.iter())
             }
             (Value::Integer(l), Value::Integer(r)) => l.cmp(r),
         }
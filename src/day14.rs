@@ -5,8 +5,12 @@ use itertools::Itertools;
 use std::error::Error;
 
 use crate::common::{
+    automaton::{Automaton, Cell, GridAutomaton},
     day,
-    grid::{Grid, GridOperationError},
+    grid::GridOperationError,
+    growable::{GrowableGrid, Growth},
+    image::{self, Colour},
+    terminal::{self, Viewport},
 };
 
 pub struct Day14 {
@@ -80,14 +84,6 @@ impl Path {
                 .flat_map(|(s, e)| expand_path_segment(s, e).skip(1)),
         )
     }
-
-    fn max_x(&self) -> usize {
-        self.0.iter().map(|(x, _)| *x).max().unwrap_or(0)
-    }
-
-    fn max_y(&self) -> usize {
-        self.0.iter().map(|(_, y)| *y).max().unwrap_or(0)
-    }
 }
 
 fn expand_path_segment<'a>(
@@ -127,71 +123,65 @@ fn test_expand_path() {
     );
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 enum Space {
+    #[default]
     Air,
     Rock,
     Sand,
 }
 
-impl Default for Space {
-    fn default() -> Self {
-        Space::Air
+use self::Space::*;
+
+fn space_colour(space: &Space) -> Colour {
+    match space {
+        Air => Colour::BLACK,
+        Rock => Colour::grey(128),
+        Sand => Colour::new(230, 200, 90),
     }
 }
 
-use self::Space::*;
+const SOURCE: (i64, i64) = (500, 0);
 
-fn add_path_to_grid(path: &Path, grid: &mut Grid<Space>) -> Result<(), GridOperationError> {
+fn add_path_to_grid(path: &Path, grid: &mut GrowableGrid<Space>) -> Result<(), GridOperationError> {
     for (x, y) in path.expand() {
-        grid.set(x, y, Rock)?;
+        grid.set(x as i64, y as i64, Rock)?;
     }
     Ok(())
 }
 
-fn build_grid(paths: &[Path], with_floor: bool) -> Result<Grid<Space>, GridOperationError> {
-    let width =
-        paths.iter().map(|p| p.max_x()).max().unwrap_or(0) + if with_floor { 100 } else { 1 };
-    let height =
-        paths.iter().map(|p| p.max_y()).max().unwrap_or(0) + if with_floor { 3 } else { 1 };
-
-    let mut grid = Grid::new(width, height);
-
+/// The cave, grown to fit the rocks and the sand source
+fn build_grid(paths: &[Path]) -> Result<GrowableGrid<Space>, GridOperationError> {
+    let mut grid = GrowableGrid::new(Growth::Exact);
     for path in paths {
         add_path_to_grid(path, &mut grid)?
     }
-
-    if with_floor {
-        // I'm going to assume there are at least 400 x off to the left to play with
-        // Sample input and my own seem to support this
-        add_path_to_grid(
-            &Path(vec![(0, height - 1), (width - 1, height - 1)]),
-            &mut grid,
-        )?;
-    }
-
+    grid.set(SOURCE.0, SOURCE.1, Air)?;
     Ok(grid)
 }
 
+/// The y of the lowest rock, past which falling sand keeps falling forever
+fn abyss(grid: &GrowableGrid<Space>) -> i64 {
+    grid.bounds().map(|(_, (_, max_y))| max_y).unwrap_or(0)
+}
+
 #[cfg(test)]
-fn render_grid(grid: &Grid<Space>) -> String {
-    let mut lines: Vec<String> = Vec::new();
-    for y in 0..grid.height() {
-        let mut line = Vec::new();
-        for x in 0..grid.width() {
-            if x == 500 && y == 0 {
-                line.push('+')
-            } else {
-                line.push(match *grid.get(x, y).unwrap_or(&Air) {
-                    Air => '.',
-                    Rock => '#',
-                    Sand => 'o',
-                })
-            }
-        }
-        lines.push(line.into_iter().collect());
+fn render_grid(grid: &GrowableGrid<Space>) -> String {
+    let mut chars = grid.grid().map(|s| match s {
+        Air => '.',
+        Rock => '#',
+        Sand => 'o',
+    });
+    let (x, y) = grid.origin();
+    if let Ok(source) = chars.get_mut((SOURCE.0 - x) as usize, (SOURCE.1 - y) as usize) {
+        *source = '+';
     }
-    lines.into_iter().join("\n")
+    chars
+        .fold_rows(String::new(), |mut line, c| {
+            line.push(*c);
+            line
+        })
+        .join("\n")
 }
 
 #[test]
@@ -199,9 +189,8 @@ fn test_grid_build() {
     let input = "498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9";
     let paths = parse_input(input).expect("Input should parse");
-    let grid = build_grid(&paths, false).expect("Grid should build");
+    let grid = build_grid(&paths).expect("Grid should build");
     let rendered = render_grid(&grid);
-    let rendered = rendered.lines().map(|l| &l[494..]).join("\n");
     assert_eq!(
         rendered.as_str(),
         "......+...
@@ -218,20 +207,20 @@ fn test_grid_build() {
 }
 
 enum StepResult {
-    Try(usize, usize),
+    Try(i64, i64),
     Stop,
     Void,
 }
 
-fn fill_sand(grid: &mut Grid<Space>) -> Result<usize, GridOperationError> {
+fn fill_sand(grid: &mut GrowableGrid<Space>, abyss: i64) -> Result<usize, GridOperationError> {
     let mut sand_quantity = 0;
     loop {
-        if grid.get(500, 0)? == &Sand {
+        if grid.get(SOURCE.0, SOURCE.1) == &Sand {
             // cave is full
             return Ok(sand_quantity);
         }
 
-        if drop_sand((500, 0), grid)? {
+        if drop_sand(SOURCE, grid, abyss)? {
             sand_quantity += 1;
         } else {
             // no sand came to rest, all done!
@@ -240,9 +229,13 @@ fn fill_sand(grid: &mut Grid<Space>) -> Result<usize, GridOperationError> {
     }
 }
 
-fn drop_sand(sand: (usize, usize), grid: &mut Grid<Space>) -> Result<bool, GridOperationError> {
-    match step_sand(sand, grid)? {
-        StepResult::Try(x, y) => drop_sand((x, y), grid),
+fn drop_sand(
+    sand: (i64, i64),
+    grid: &mut GrowableGrid<Space>,
+    abyss: i64,
+) -> Result<bool, GridOperationError> {
+    match step_sand(sand, grid, abyss) {
+        StepResult::Try(x, y) => drop_sand((x, y), grid, abyss),
         StepResult::Stop => {
             grid.set(sand.0, sand.1, Sand)?;
             Ok(true)
@@ -251,49 +244,74 @@ fn drop_sand(sand: (usize, usize), grid: &mut Grid<Space>) -> Result<bool, GridO
     }
 }
 
-fn step_sand(sand: (usize, usize), grid: &Grid<Space>) -> Result<StepResult, GridOperationError> {
+fn step_sand(sand: (i64, i64), grid: &GrowableGrid<Space>, abyss: i64) -> StepResult {
     use StepResult::*;
-    let below = (sand.0, sand.1 + 1);
-    if below.1 == grid.height() {
-        return Ok(Void);
-    }
-
-    match grid.get(below.0, below.1)? {
-        Air => Ok(Try(below.0, below.1)),
-        _ => {
-            // try to go left first
-            if below.0 == 0 {
-                // falls into the void
-                Ok(Void)
-            } else {
-                let below_left = (below.0 - 1, below.1);
-                if grid.get(below_left.0, below_left.1)? == &Air {
-                    Ok(Try(below_left.0, below_left.1))
-                } else {
-                    let below_right = (below.0 + 1, below.1);
-                    if below_right.0 == grid.width() {
-                        Ok(Void)
-                    } else if grid.get(below_right.0, below_right.1)? == &Air {
-                        Ok(Try(below_right.0, below_right.1))
-                    } else {
-                        Ok(Stop)
-                    }
-                }
-            }
-        }
+    let y = sand.1 + 1;
+    if y > abyss {
+        return Void;
     }
+    // straight down, then down and left, then down and right
+    [sand.0, sand.0 - 1, sand.0 + 1]
+        .into_iter()
+        .find(|x| grid.get(*x, y) == &Air)
+        .map(|x| Try(x, y))
+        .unwrap_or(Stop)
 }
 
 fn run_part1(input: &str) -> Result<usize, Box<dyn Error>> {
-    let mut grid = build_grid(&parse_input(input)?, false)?;
-    let sand = fill_sand(&mut grid)?;
+    let mut grid = build_grid(&parse_input(input)?)?;
+    let abyss = abyss(&grid);
+    let sand = fill_sand(&mut grid, abyss)?;
     Ok(sand)
 }
 
+/// Air fills up if sand above it, or diagonally above it, could fall into it
+fn spread_sand(cell: &Cell<Space>) -> Space {
+    match cell.value() {
+        Air if (-1..=1).any(|dx| cell.offset(dx, -1) == Some(&Sand)) => Sand,
+        space => *space,
+    }
+}
+
+/// With a floor, every space sand can reach ends up full before the source is blocked,
+/// so rather than dropping one grain at a time, spread sand a row further down each
+/// generation until nothing changes
 fn run_part2(input: &str) -> Result<usize, Box<dyn Error>> {
-    let mut grid = build_grid(&parse_input(input)?, true)?;
-    let sand = fill_sand(&mut grid)?;
-    Ok(sand)
+    let paths = parse_input(input)?;
+    let floor = abyss(&build_grid(&paths)?) + 2;
+    // unlike part 1 the cave can't grow as sand reaches its edge, because the
+    // automaton works out each generation across the whole of a fixed grid, so
+    // every cell sand could reach has to be there before it starts. Sand moves at
+    // most one column sideways for each row it falls, so nothing outside this
+    // triangle's bounding box can fill up or get in the way.
+    let mut cave = GrowableGrid::with_bounds(
+        (SOURCE.0 - floor, SOURCE.1),
+        (SOURCE.0 + floor, floor - 1),
+        Growth::Fixed,
+    )
+    .expect("the floor is below the source");
+    for (x, y) in paths.iter().flat_map(|path| path.expand()) {
+        let (x, y) = (x as i64, y as i64);
+        if cave.contains(x, y) {
+            cave.set(x, y, Rock)?;
+        }
+    }
+    cave.set(SOURCE.0, SOURCE.1, Sand)?;
+
+    let mut automaton = GridAutomaton::new(cave.into_grid(), spread_sand).parallel(true);
+    // sand only ever spreads, so this always settles
+    automaton.run(usize::MAX, false);
+    let grid = automaton.into_grid();
+    if terminal::wants_drawing() {
+        println!(
+            "{}",
+            terminal::render_braille(&grid, &Viewport::terminal(), |s| *s != Air)
+        );
+    }
+    if let Some(path) = image::output_path("day14-sand.png") {
+        image::save(&grid, path, 2, space_colour)?;
+    }
+    Ok(grid.count(|s| *s == Sand))
 }
 
 #[test]
@@ -304,6 +322,14 @@ fn test_part1_sample() {
     assert_eq!(sand, 24);
 }
 
+#[test]
+fn test_part2_sample() {
+    let input = "498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9";
+    let sand = run_part2(input).expect("No errors");
+    assert_eq!(sand, 93);
+}
+
 #[test]
 fn test_drop_five_sand() {
     let rendered = drop_n_sand(5);
@@ -363,11 +389,10 @@ fn drop_n_sand(n: usize) -> String {
     let input = "498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9";
     let paths = parse_input(input).expect("Input should parse");
-    let mut grid = build_grid(&paths, false).expect("Grid should build");
+    let mut grid = build_grid(&paths).expect("Grid should build");
+    let abyss = abyss(&grid);
     for _ in 0..n {
-        drop_sand((500, 0), &mut grid).expect("No errors");
+        drop_sand(SOURCE, &mut grid, abyss).expect("No errors");
     }
-    let rendered = render_grid(&grid);
-    let rendered = rendered.lines().map(|l| &l[494..]).join("\n");
-    rendered
+    render_grid(&grid)
 }
@@ -1,9 +1,11 @@
-use std::{collections::HashSet, error::Error, ops::RangeInclusive};
+use std::{collections::HashSet, error::Error};
 
-use range_set::RangeSet;
 use rayon::prelude::*;
 
-use crate::common::day;
+use crate::common::{
+    day,
+    interval::{Interval, IntervalSet},
+};
 
 pub struct Day15 {
     input: &'static str,
@@ -66,7 +68,7 @@ impl Sensor {
         }
     }
 
-    fn can_confirm_no_beacons_at_y(&self, y: i32) -> Option<Range> {
+    fn can_confirm_no_beacons_at_y(&self, y: i32) -> Option<Interval> {
         let neutral_position = Position::new(self.position.x, y);
         let neutral_distance = self.position.distance_from(&neutral_position);
         if neutral_distance > self.beacon_range {
@@ -78,42 +80,7 @@ impl Sensor {
         let min_x_in_range = neutral_position.x - available_range;
         let max_x_in_range = neutral_position.x + available_range;
 
-        Some(Range::new(min_x_in_range, max_x_in_range))
-    }
-}
-
-#[derive(Clone)]
-struct Range {
-    lower: i32,
-    upper: i32,
-}
-
-impl Range {
-    fn new(lower: i32, upper: i32) -> Self {
-        Range { lower, upper }
-    }
-
-    fn combine_with(&self, other: &Range) -> Option<Range> {
-        if self.lower <= other.lower && self.upper >= other.upper {
-            // other is totally contained within self
-            Some(self.clone())
-        } else if self.lower <= other.lower && self.upper >= other.lower {
-            // overlapping - we are lower
-            Some(Range::new(self.lower, other.upper))
-        } else if self.lower >= other.lower && self.upper >= other.upper {
-            // overlapping - we are higher
-            Some(Range::new(other.lower, self.upper))
-        } else if self.lower >= other.lower && self.upper <= other.upper {
-            // self is totally contained within other
-            Some(other.clone())
-        } else {
-            // no overlap, cannot combine
-            None
-        }
-    }
-
-    fn len(&self) -> usize {
-        (0 - self.lower).unsigned_abs() as usize + self.upper.unsigned_abs() as usize
+        Interval::new(min_x_in_range.into(), max_x_in_range.into())
     }
 }
 
@@ -152,84 +119,29 @@ fn parse_input(input: &str) -> Result<Vec<Sensor>, Box<dyn Error>> {
 fn find_positions_at_y_where_no_beacons_can_be_present<'a>(
     sensors: impl Iterator<Item = &'a Sensor>,
     y: i32,
-) -> Vec<Range> {
-    let mut ranges: Vec<Range> = Vec::new();
+) -> IntervalSet {
+    let mut covered = Vec::new();
     let mut beacon_positions = HashSet::new();
     for sensor in sensors {
         if sensor.beacon.y == y {
             beacon_positions.insert(sensor.beacon.x);
         }
-        if let Some(range) = sensor.can_confirm_no_beacons_at_y(y) {
-            let mut combined_range = false;
-            for existing_range in ranges.iter_mut() {
-                if let Some(new_range) = existing_range.combine_with(&range) {
-                    *existing_range = new_range;
-                    combined_range = true;
-                    break;
-                }
-            }
-            if !combined_range {
-                ranges.push(range);
-            }
-        }
+        covered.extend(sensor.can_confirm_no_beacons_at_y(y));
     }
-    normalise_ranges(&ranges)
+    // the beacons themselves are the only places on the row a sensor can see a beacon
+    let covered = covered.into_iter().collect::<IntervalSet>();
+    let beacons = beacon_positions
+        .into_iter()
+        .map(|x| Interval::point(x.into()))
+        .collect::<IntervalSet>();
+    covered.difference(&beacons)
 }
 
 fn count_positions_at_y_where_no_beacons_can_be_present<'a>(
     sensors: impl Iterator<Item = &'a Sensor>,
     y: i32,
 ) -> usize {
-    find_positions_at_y_where_no_beacons_can_be_present(sensors, y)
-        .into_iter()
-        .map(|r| r.len())
-        .sum()
-}
-
-fn normalise_ranges(ranges: &Vec<Range>) -> Vec<Range> {
-    if ranges.len() <= 1 {
-        return ranges.clone();
-    }
-
-    fn normalise_pass(range: Range, others: Vec<Range>) -> (Vec<Range>, bool) {
-        if others.is_empty() {
-            return (vec![range], false);
-        }
-
-        let mut ranges = Vec::new();
-        let mut did_combine = false;
-        for other in others {
-            if let Some(combined) = range.combine_with(&other) {
-                ranges.push(combined);
-                did_combine = true;
-            } else {
-                ranges.push(other);
-            }
-        }
-        if !did_combine {
-            ranges.push(range)
-        }
-        (ranges, did_combine)
-    }
-
-    let mut working_ranges = ranges.clone();
-    working_ranges.sort_by_key(|r| r.lower);
-    loop {
-        if working_ranges.len() <= 1 {
-            return working_ranges;
-        }
-
-        let this_range = working_ranges
-            .drain(..1)
-            .next()
-            .expect("We already checked there was at least one range in here");
-
-        let (new_ranges, did_combine) = normalise_pass(this_range, working_ranges);
-        if !did_combine {
-            return new_ranges;
-        }
-        working_ranges = new_ranges;
-    }
+    find_positions_at_y_where_no_beacons_can_be_present(sensors, y).coverage() as usize
 }
 
 #[test]
@@ -257,20 +169,17 @@ fn find_beacon_in_range(min: i32, max: i32, sensors: &[Sensor]) -> Result<Positi
 }
 
 fn values_not_covered_by(min: i32, max: i32, sensors: &[Sensor], y: i32) -> Option<Position> {
-    let beacon_not_here = sensors
+    let covered = sensors
         .iter()
-        .filter_map(|s| s.can_confirm_no_beacons_at_y(y));
-
-    let mut rangeset = RangeSet::<[RangeInclusive<i32>; 2]>::from(min..=max);
-    for not_here in beacon_not_here {
-        rangeset.remove_range(not_here.lower..=not_here.upper);
-    }
-
-    if let Some(beacon_x_range) = rangeset.remove_range(min..=max) {
-        return beacon_x_range.iter().map(|x| Position::new(x, y)).next();
-    }
-
-    None
+        .filter_map(|s| s.can_confirm_no_beacons_at_y(y))
+        .collect::<IntervalSet>();
+    let search = Interval::new(min.into(), max.into())?;
+
+    IntervalSet::from(search)
+        .difference(&covered)
+        .intervals()
+        .first()
+        .map(|gap| Position::new(gap.lower() as i32, y))
 }
 
 fn tuning_frequency(beacon: &Position) -> i64 {
@@ -1,8 +1,10 @@
 use petgraph::{algo::dijkstra, prelude::GraphMap, Undirected};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     error::Error,
+    rc::Rc,
 };
 use string_interner::StringInterner;
 
@@ -102,8 +104,11 @@ fn make_valve_distance_map(valves: &HashSet<Symbol>, valve_graph: &ValveGraph) -
         .collect()
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "SavedSystem", try_from = "SavedSystem")]
 struct System {
+    /// The valve names, shared between all the clones made while searching
+    names: Rc<StringInterner>,
     open_valves: HashSet<Symbol>,
     closed_valves: HashSet<Symbol>,
     valve_distances: ValveDistances,
@@ -131,6 +136,7 @@ impl System {
             .ok_or("couldn't find starting valve 'AA'")?;
 
         Ok(Self {
+            names: Rc::new(interner),
             open_valves: HashSet::new(),
             closed_valves: valves,
             valve_distances,
@@ -210,6 +216,121 @@ impl System {
     }
 }
 
+/// A System with its valves named rather than interned, so it means something outside
+/// of the interner it was made with
+#[derive(Serialize, Deserialize)]
+struct SavedSystem {
+    open_valves: BTreeSet<String>,
+    closed_valves: BTreeSet<String>,
+    valve_distances: BTreeMap<String, BTreeMap<String, u32>>,
+    valve_flows: BTreeMap<String, u32>,
+    time_remaining: u32,
+    current_valve: String,
+    water_drained: u32,
+    flow_rate: u32,
+}
+
+impl From<System> for SavedSystem {
+    fn from(system: System) -> Self {
+        let name = |v: &Symbol| {
+            system
+                .names
+                .resolve(*v)
+                .expect("every valve was interned when the system was made")
+                .to_owned()
+        };
+        let names = |valves: &HashSet<Symbol>| valves.iter().map(name).collect();
+        Self {
+            open_valves: names(&system.open_valves),
+            closed_valves: names(&system.closed_valves),
+            valve_distances: system
+                .valve_distances
+                .iter()
+                .map(|(from, distances)| {
+                    (
+                        name(from),
+                        distances.iter().map(|(to, d)| (name(to), *d)).collect(),
+                    )
+                })
+                .collect(),
+            valve_flows: system
+                .valve_flows
+                .iter()
+                .map(|(v, flow)| (name(v), *flow))
+                .collect(),
+            time_remaining: system.time_remaining,
+            current_valve: name(&system.current_valve),
+            water_drained: system.water_drained,
+            flow_rate: system.flow_rate,
+        }
+    }
+}
+
+impl TryFrom<SavedSystem> for System {
+    type Error = String;
+
+    fn try_from(saved: SavedSystem) -> Result<Self, Self::Error> {
+        let mut interner = StringInterner::new();
+        let mut symbols = |valves: BTreeSet<String>| {
+            valves
+                .into_iter()
+                .map(|v| interner.get_or_intern(v))
+                .collect::<HashSet<_>>()
+        };
+        let open_valves = symbols(saved.open_valves);
+        let closed_valves = symbols(saved.closed_valves);
+        let valve_flows = saved
+            .valve_flows
+            .into_iter()
+            .map(|(v, flow)| (interner.get_or_intern(v), flow))
+            .collect::<HashMap<_, _>>();
+        let valve_distances = saved
+            .valve_distances
+            .into_iter()
+            .map(|(from, distances)| {
+                let distances = distances
+                    .into_iter()
+                    .map(|(to, d)| (interner.get_or_intern(to), d))
+                    .collect();
+                (interner.get_or_intern(from), distances)
+            })
+            .collect::<ValveDistances>();
+        let current_valve = interner.get_or_intern(saved.current_valve);
+
+        // everything the search looks up has to be there, or it would panic later on
+        for valve in open_valves.iter().chain(closed_valves.iter()) {
+            if !valve_flows.contains_key(valve) {
+                return Err(format!(
+                    "Valve {} has no flow rate",
+                    interner.resolve(*valve).unwrap_or_default()
+                ));
+            }
+            if !valve_distances
+                .get(&current_valve)
+                .map(|d| d.contains_key(valve))
+                .unwrap_or(false)
+            {
+                return Err(format!(
+                    "No distance from the current valve to {}",
+                    interner.resolve(*valve).unwrap_or_default()
+                ));
+            }
+        }
+
+        Ok(Self {
+            names: Rc::new(interner),
+            open_valves,
+            closed_valves,
+            valve_distances,
+            valve_flows,
+            time_remaining: saved.time_remaining,
+            current_valve,
+            water_drained: saved.water_drained,
+            flow_rate: saved.flow_rate,
+        })
+    }
+}
+
 fn run_part1(input: &str) -> Result<u32, Box<dyn Error>> {
     let mut system = System::from_input(input)?;
     let paths = system.step();
@@ -219,9 +340,8 @@ fn run_part1(input: &str) -> Result<u32, Box<dyn Error>> {
         .ok_or_else(|| "No paths found".into())
 }
 
-#[test]
-fn test_part1_sample() {
-    let input = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
+#[cfg(test)]
+const TEST_INPUT: &str = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
 Valve BB has flow rate=13; tunnels lead to valves CC, AA
 Valve CC has flow rate=2; tunnels lead to valves DD, BB
 Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
@@ -232,6 +352,40 @@ Valve HH has flow rate=22; tunnel leads to valve GG
 Valve II has flow rate=0; tunnels lead to valves AA, JJ
 Valve JJ has flow rate=21; tunnel leads to valve II";
 
-    let result = run_part1(input).expect("Expect a result");
+#[test]
+fn test_part1_sample() {
+    let result = run_part1(TEST_INPUT).expect("Expect a result");
     assert_eq!(result, 1651);
 }
+
+#[test]
+fn test_system_checkpoint() {
+    use crate::common::serialise;
+
+    let mut system = System::from_input(TEST_INPUT).unwrap();
+    let dd = system.names.get("DD").unwrap();
+    let distance = system.distance_to(&dd);
+    system.drain_water_for(distance);
+    system.current_valve = dd;
+    system.open_valve(&dd);
+
+    let text = serialise::to_text(&system).unwrap();
+    let mut restored: System = serialise::from_text(&text).unwrap();
+    assert_eq!(restored.time_remaining, 28);
+    assert_eq!(restored.flow_rate, 20);
+    assert_eq!(
+        restored
+            .open_valves
+            .iter()
+            .map(|v| restored.names.resolve(*v))
+            .collect::<Vec<_>>(),
+        vec![Some("DD")]
+    );
+    // searching on from the restored state finds the same best path as the original
+    let best = |s: &mut System| s.step().into_iter().max();
+    assert_eq!(best(&mut restored), best(&mut system));
+
+    let bytes = serialise::to_binary(&system).unwrap();
+    let mut restored: System = serialise::from_binary(&bytes).unwrap();
+    assert_eq!(best(&mut restored), Some(1651));
+}
@@ -1,5 +1,5 @@
-use crate::common::day;
-use rayon::prelude::*;
+use crate::common::{bitgrid::BitGrid, day};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::ops::Add;
@@ -16,7 +16,7 @@ pub fn run() -> day::Result {
     ))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 struct Point {
     x: i64,
     y: i64,
@@ -108,18 +108,58 @@ fn parse_jet(input: &str) -> impl Iterator<Item = Jet> + '_ {
     })
 }
 
-#[derive(Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "ChamberData")]
 struct Chamber<const W: usize> {
-    rocks: Vec<[bool; W]>,
+    rocks: BitGrid,
     falling_rocks: HashSet<Point>,
     max_rock_height: i64,
     purge_offset: i64,
 }
 
+/// The fields of a Chamber as they're stored, checked against its width before becoming one
+#[derive(Deserialize)]
+struct ChamberData {
+    rocks: BitGrid,
+    falling_rocks: HashSet<Point>,
+    max_rock_height: i64,
+    purge_offset: i64,
+}
+
+impl<const W: usize> TryFrom<ChamberData> for Chamber<W> {
+    type Error = String;
+
+    fn try_from(data: ChamberData) -> Result<Self, Self::Error> {
+        if data.rocks.width() != W {
+            return Err(format!(
+                "A chamber {} wide can't hold rocks {} wide",
+                W,
+                data.rocks.width()
+            ));
+        }
+        if let Some(p) = data
+            .falling_rocks
+            .iter()
+            .find(|p| p.x < 0 || p.x >= W as i64)
+        {
+            return Err(format!(
+                "A falling rock at {},{} is outside a chamber {} wide",
+                p.x, p.y, W
+            ));
+        }
+        Ok(Self {
+            rocks: data.rocks,
+            falling_rocks: data.falling_rocks,
+            max_rock_height: data.max_rock_height,
+            purge_offset: data.purge_offset,
+        })
+    }
+}
+
 impl<const W: usize> Chamber<W> {
     fn new() -> Self {
         Self {
-            rocks: Vec::with_capacity(20000),
+            rocks: BitGrid::new(W, 0),
             falling_rocks: HashSet::new(),
             max_rock_height: -1,
             purge_offset: 0,
@@ -145,15 +185,16 @@ impl<const W: usize> Chamber<W> {
     }
 
     fn add_stopped_rock(&mut self, rock: Point) {
-        if self.rocks.len() <= rock.y as usize {
-            // ensure enough rows are in the Vec
-            for _ in self.rocks.len()..=(rock.y as usize + 1) {
-                self.rocks.push([false; W]);
-            }
+        if self.rocks.height() <= rock.y as usize {
+            // ensure enough rows are in the grid
+            self.rocks
+                .push_empty_rows(rock.y as usize + 2 - self.rocks.height());
         }
 
         // update appropriate value
-        self.rocks[rock.y as usize][rock.x as usize] = true;
+        self.rocks
+            .set(rock.x as usize, rock.y as usize, true)
+            .expect("rows were added above so the rock is within the grid");
 
         self.max_rock_height = i64::max(self.max_rock_height, rock.y);
     }
@@ -211,22 +252,26 @@ impl<const W: usize> Chamber<W> {
 
         // remove everything below that y which we haven't
         // previously removed
-        self.rocks.drain(0..(y as usize));
+        self.rocks
+            .remove_rows(0..(y as usize))
+            .expect("the full row was found in the grid so every row below it is too");
 
         self.purge_offset += y;
         self.max_rock_height -= y;
     }
 
     fn is_full_at(&self, y: i64) -> bool {
-        self.get_at_y(y) == &[true; W]
-    }
-
-    fn get_at_y(&self, y: i64) -> &[bool; W] {
-        self.rocks.get(y as usize).unwrap_or(&[false; W])
+        usize::try_from(y)
+            .ok()
+            .and_then(|y| self.rocks.is_row_full(y).ok())
+            .unwrap_or(false)
     }
 
     fn rock_at(&self, x: usize, y: i64) -> bool {
-        self.get_at_y(y)[x]
+        usize::try_from(y)
+            .ok()
+            .and_then(|y| self.rocks.get(x, y).ok())
+            .unwrap_or(false)
     }
 
     fn rock_at_point(&self, p: &Point) -> bool {
@@ -278,7 +323,7 @@ impl<const W: usize> Display for Chamber<W> {
 struct Memo<const W: usize> {
     piece_index: usize,
     jet_index: usize,
-    rocks: Vec<[bool; W]>,
+    rocks: BitGrid,
 }
 
 #[derive(Debug)]
@@ -288,7 +333,6 @@ struct MemoData {
 
 struct MemoisedChamber<const W: usize> {
     memory: HashMap<Memo<W>, MemoData>,
-    memo_hits: HashMap<Memo<W>, usize>,
     chamber: Chamber<W>,
     pieces: Vec<Piece>,
     jets: Vec<Jet>,
@@ -296,7 +340,6 @@ struct MemoisedChamber<const W: usize> {
     current_jet: usize,
     rock_height: u64,
     is_repeating: bool,
-    is_repeating_repeating: bool,
 }
 
 impl<const W: usize> MemoisedChamber<W> {
@@ -310,8 +353,6 @@ impl<const W: usize> MemoisedChamber<W> {
             current_jet: 0,
             rock_height: 0,
             is_repeating: false,
-            is_repeating_repeating: false,
-            memo_hits: HashMap::new(),
         }
     }
 
@@ -358,7 +399,7 @@ impl<const W: usize> MemoisedChamber<W> {
             if self.chamber.drop_rocks() {
                 self.chamber.prune();
                 let added_height = self.chamber.rock_height() - start_height;
-                let e = self.memory.insert(
+                self.memory.insert(
                     Memo {
                         piece_index: start_piece,
                         jet_index: start_jet,
@@ -407,3 +448,30 @@ fn test_part1_sample() {
         3068
     );
 }
+
+#[test]
+fn test_chamber_checkpoint() {
+    use crate::common::serialise;
+
+    let jets: Vec<Jet> = parse_jet(">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>").collect();
+    let mut chamber: MemoisedChamber<7> = MemoisedChamber::new(pieces().collect(), jets);
+    for _ in 0..30 {
+        chamber.run_rock();
+    }
+    // leave a rock part way down so the falling rocks are saved too
+    let piece = chamber.get_next_piece();
+    chamber.chamber.add_rock(&piece);
+
+    let text = serialise::to_text(&chamber.chamber).unwrap();
+    let from_text: Chamber<7> = serialise::from_text(&text).unwrap();
+    assert_eq!(from_text, chamber.chamber);
+    assert_eq!(from_text.to_string(), chamber.chamber.to_string());
+
+    let bytes = serialise::to_binary(&chamber.chamber).unwrap();
+    let from_binary: Chamber<7> = serialise::from_binary(&bytes).unwrap();
+    assert_eq!(from_binary, chamber.chamber);
+
+    // a chamber saved at one width can't be loaded as another
+    assert!(serialise::from_text::<Chamber<5>>(&text).is_err());
+    assert!(serialise::from_binary::<Chamber<9>>(&bytes).is_err());
+}
@@ -1,5 +1,9 @@
-use crate::common::day;
-use std::{collections::HashSet, str::FromStr};
+use crate::common::{
+    day,
+    flood::{enclosed_regions, label_voxels},
+    voxel::{Point3, VoxelSet},
+};
+use std::str::FromStr;
 
 pub fn run() -> day::Result {
     let input = include_str!("inputs/day18.txt");
@@ -12,85 +16,36 @@ pub fn run() -> day::Result {
     ))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Cube {
-    x: i32,
-    y: i32,
-    z: i32,
-}
-
-impl Cube {
-    fn new(x: i32, y: i32, z: i32) -> Self {
-        Self { x, y, z }
-    }
-
-    fn surrounding(&self) -> [Cube; 6] {
-        [
-            (-1, 0, 0),
-            (1, 0, 0),
-            (0, -1, 0),
-            (0, 1, 0),
-            (0, 0, -1),
-            (0, 0, 1),
-        ]
-        .map(|v| *self + v)
-    }
-}
-
-impl std::ops::Add<(i32, i32, i32)> for Cube {
-    type Output = Cube;
-
-    fn add(self, (x, y, z): (i32, i32, i32)) -> Self::Output {
-        Cube {
-            x: self.x + x,
-            y: self.y + y,
-            z: self.z + z,
-        }
-    }
-}
-
 struct Blob {
-    cubes: HashSet<Cube>,
+    cubes: VoxelSet,
 }
 
 impl Blob {
-    fn from_cubes(i: impl Iterator<Item = Cube>) -> Self {
+    fn from_cubes(i: impl Iterator<Item = Point3>) -> Self {
         Self { cubes: i.collect() }
     }
 
     fn count_exposed_faces(&self) -> usize {
-        let mut exposed_faces = self.cubes.len() * 6;
-
-        for cube in self.cubes.iter() {
-            // discount every surrounding cube that is in the blob
-            // this should work with overlapping neighbours as we already counted overlapping labels
-            exposed_faces -= cube
-                .surrounding()
-                .into_iter()
-                .filter(|s| self.cubes.contains(s))
-                .count()
-        }
-
-        exposed_faces
-    }
-
-    fn exposed_face_cubes(&self) -> impl Iterator<Item = Cube> + '_ {
-        self.cubes.iter().flat_map(|c| {
-            c.surrounding()
-                .into_iter()
-                .filter(|sc| !self.cubes.contains(sc))
-        })
+        self.cubes.surface_area()
     }
 
     fn external_surface_area(&self) -> usize {
-        let exposed_cubes: Vec<Cube> = self.exposed_face_cubes().collect();
-        exposed_cubes
-            .iter()
-            .filter(|c| {
-                c.surrounding()
-                    .into_iter()
-                    .any(|sc| !self.cubes.contains(&sc) && !exposed_cubes.contains(&sc))
-            })
+        // a dense copy of the blob is quicker to look cubes up in while flooding
+        let solid = match self.cubes.to_grid() {
+            Some(grid) => grid,
+            None => return 0,
+        };
+
+        // flood the air in a box one larger than the blob on every side, so the
+        // exterior is a single region and any other air is trapped inside
+        let air = label_voxels(solid.bounds().expand(1), |p| {
+            !solid.get(p).copied().unwrap_or(false)
+        });
+        let pockets = enclosed_regions(&air).collect::<Vec<_>>();
+
+        self.cubes
+            .exposed_faces()
+            .filter(|p| !pockets.iter().any(|r| r.contains(p)))
             .count()
     }
 }
@@ -105,7 +60,7 @@ impl FromStr for Blob {
                     let mut parts = line.trim().split(',');
                     if let (Some(x), Some(y), Some(z)) = (parts.next(), parts.next(), parts.next())
                     {
-                        Ok::<Cube, Box<dyn std::error::Error>>(Cube::new(
+                        Ok::<Point3, Box<dyn std::error::Error>>(Point3::new(
                             x.parse()?,
                             y.parse()?,
                             z.parse()?,
@@ -30,7 +30,7 @@ impl day::Day for Day3 {
 
 fn split_rucksack(rucksack: &str) -> Option<(&str, &str)> {
     let len = rucksack.len();
-    if len % 2 != 0 {
+    if !len.is_multiple_of(2) {
         return None;
     }
     Some((&rucksack[0..len / 2], &rucksack[len / 2..]))
@@ -52,8 +52,8 @@ fn duplicates_in_input(input: &str) -> impl Iterator<Item = char> + '_ {
 
 fn item_priority(i: char) -> u8 {
     match i {
-        x if ('a'..='z').contains(&x) => (i as u8 - b'a') + 1,
-        x if ('A'..='Z').contains(&x) => (i as u8 - b'A') + 27,
+        x if x.is_ascii_lowercase() => (i as u8 - b'a') + 1,
+        x if x.is_ascii_uppercase() => (i as u8 - b'A') + 27,
         _ => 0,
     }
 }
@@ -1,5 +1,5 @@
-use crate::common::day;
-use std::{collections::HashSet, fmt::Debug, str::FromStr};
+use crate::common::{day, interval::Interval};
+use std::{fmt::Debug, str::FromStr};
 
 pub struct Day4 {
     input: &'static str,
@@ -30,51 +30,27 @@ impl day::Day for Day4 {
     }
 }
 
-#[derive(PartialEq, Eq, Clone)]
-struct Assignment {
-    lower: u32,
-    upper: u32,
-}
-
-impl Assignment {
-    fn expand(&self) -> HashSet<u32> {
-        let mut s = HashSet::new();
-        for n in self.lower..=self.upper {
-            s.insert(n);
-        }
-        s
-    }
-}
-
-impl Debug for Assignment {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}-{}", self.lower, self.upper)
-    }
-}
-
-impl FromStr for Assignment {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split('-');
-        let lower_str = parts
-            .next()
-            .ok_or_else(|| format!("Input string {} didn't contain a first part", s))?;
-        let upper_str = parts
-            .next()
-            .ok_or_else(|| format!("Input string {} didn't contain a second part", s))?;
-        let lower =
-            u32::from_str(lower_str).map_err(|_| format!("Lower bound {} not a u32", lower_str))?;
-        let upper =
-            u32::from_str(upper_str).map_err(|_| format!("Upper bound {} not a u32", upper_str))?;
-        Ok(Assignment { lower, upper })
-    }
+/// The range of section IDs assigned to one elf, written like `2-4`
+fn parse_assignment(s: &str) -> Result<Interval, String> {
+    let mut parts = s.split('-');
+    let lower_str = parts
+        .next()
+        .ok_or_else(|| format!("Input string {} didn't contain a first part", s))?;
+    let upper_str = parts
+        .next()
+        .ok_or_else(|| format!("Input string {} didn't contain a second part", s))?;
+    let lower =
+        u32::from_str(lower_str).map_err(|_| format!("Lower bound {} not a u32", lower_str))?;
+    let upper =
+        u32::from_str(upper_str).map_err(|_| format!("Upper bound {} not a u32", upper_str))?;
+    Interval::new(lower.into(), upper.into())
+        .ok_or_else(|| format!("Assignment {} ends before it starts", s))
 }
 
 #[derive(PartialEq, Eq, Clone)]
 struct Pair {
-    left: Assignment,
-    right: Assignment,
+    left: Interval,
+    right: Interval,
 }
 
 impl Pair {
@@ -83,28 +59,21 @@ impl Pair {
     }
 
     fn left_fully_contains_right(&self) -> bool {
-        self.left.lower <= self.right.lower && self.left.upper >= self.right.upper
+        self.left.contains_interval(&self.right)
     }
 
     fn right_fully_contains_left(&self) -> bool {
-        self.right.lower <= self.left.lower && self.right.upper >= self.left.upper
+        self.right.contains_interval(&self.left)
     }
 
     fn has_overlap(&self) -> bool {
-        // okay I could do this with some bounds comparisons but I haven't had breakfast and I can't
-        // be bothered to think through all the permutations
-        // but really allocating all these unnecessary HashSets is a bit silly
-        self.left
-            .expand()
-            .intersection(&self.right.expand())
-            .next()
-            .is_some()
+        self.left.overlaps(&self.right)
     }
 }
 
 impl Debug for Pair {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?},{:?}", self.left, self.right)
+        write!(f, "{},{}", self.left, self.right)
     }
 }
 
@@ -119,8 +88,8 @@ impl FromStr for Pair {
         let right_str = parts
             .next()
             .ok_or_else(|| format!("Input string {} did not have a right assignment", s))?;
-        let left = Assignment::from_str(left_str)?;
-        let right = Assignment::from_str(right_str)?;
+        let left = parse_assignment(left_str)?;
+        let right = parse_assignment(right_str)?;
         Ok(Pair { left, right })
     }
 }
@@ -155,11 +124,8 @@ fn test_parse_pair() {
     assert_eq!(
         Pair::from_str(input).unwrap(),
         Pair {
-            left: Assignment { lower: 2, upper: 4 },
-            right: Assignment {
-                lower: 6,
-                upper: 88
-            }
+            left: Interval::new(2, 4).unwrap(),
+            right: Interval::new(6, 88).unwrap()
         }
     );
 }
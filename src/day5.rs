@@ -1,6 +1,18 @@
+mod crane;
+
+use self::crane::{Crane, CrateMover9000, CrateMover9001, FlippingCrane, LimitedCrane};
 use crate::common::day;
+use itertools::Itertools;
 use regex::Regex;
-use std::{collections::HashMap, error::Error, str::FromStr};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+    thread,
+    time::Duration,
+};
 
 pub struct Day5 {
     input: &'static str,
@@ -16,34 +28,94 @@ impl Day5 {
 
 impl day::Day for Day5 {
     fn run(&mut self) -> day::Result {
-        let moves = parse_input_instructions(self.input)?;
-        let stacks = stacks_input();
-        let part1_answer = run_part1(&moves, stacks)?;
-        let stacks = stacks_input();
+        let (stacks, moves) = parse_input(self.input)?;
+        let part1_answer = run_part1(&moves, stacks.clone())?;
         let part2_answer = run_part2(&moves, stacks)?;
         Ok((Some(part1_answer), Some(part2_answer)))
     }
 }
 
-fn run_part1(moves: &[Move], mut stacks: Stacks) -> Result<String, String> {
-    for m in moves {
-        if !stacks.run_move(m) {
-            return Err(format!("Move {:?} failed!", m));
+/// Shows the crane running the moves from the puzzle input in the terminal, waiting
+/// delay between each one. The crane can be 9000, 9001, flipping or limit=N.
+pub fn animate(crane: &str, delay: Duration) -> Result<(), Box<dyn Error>> {
+    let (stacks, moves) = parse_input(Day5::new().input)?;
+    let mut crane = crane_model(crane)?;
+    play(
+        &mut io::stdout().lock(),
+        crane.as_mut(),
+        &moves,
+        &stacks,
+        delay,
+    )
+}
+
+fn crane_model(name: &str) -> Result<Box<dyn Crane>, String> {
+    match name {
+        "9000" => Ok(Box::new(CrateMover9000)),
+        "9001" => Ok(Box::new(CrateMover9001)),
+        "flipping" => Ok(Box::<FlippingCrane>::default()),
+        _ => {
+            let capacity = name
+                .strip_prefix("limit=")
+                .and_then(|c| c.parse().ok())
+                .ok_or_else(|| format!("Unknown crane '{}'", name))?;
+            Ok(Box::new(LimitedCrane::new(capacity)?))
         }
     }
-    Ok(stacks.read_tops())
 }
 
-fn run_part2(moves: &[Move], mut stacks: Stacks) -> Result<String, String> {
-    for m in moves {
-        stacks
-            .run_move_cratemover_9001(m)
-            .ok_or_else(|| "Move failed".to_string())?;
+/// Draws each state of the stacks over the last one, with the move which led to it,
+/// then says which crates ended up on top
+fn play(
+    out: &mut impl Write,
+    crane: &mut dyn Crane,
+    moves: &[Move],
+    stacks: &Stacks,
+    delay: Duration,
+) -> Result<(), Box<dyn Error>> {
+    let states = stacks.states(crane, moves)?;
+    let height = states.iter().map(Stacks::height).max().unwrap_or(0);
+    for (i, state) in states.iter().enumerate() {
+        // clear the screen and go back to the top left
+        write!(out, "\x1b[2J\x1b[H")?;
+        match i.checked_sub(1) {
+            None => writeln!(out, "Starting stacks\n")?,
+            Some(m) => writeln!(out, "Move {} of {}: {}\n", i, moves.len(), moves[m])?,
+        }
+        write!(out, "{}", state.draw(height))?;
+        out.flush()?;
+        thread::sleep(delay);
     }
+    let tops = states.last().map(Stacks::read_tops).unwrap_or_default();
+    writeln!(
+        out,
+        "\nAfter {} moves the {} leaves {} on top",
+        moves.len(),
+        crane.name(),
+        tops
+    )?;
+    Ok(())
+}
+
+fn run_part1(moves: &[Move], stacks: Stacks) -> Result<String, MoveError> {
+    run_crane(&mut CrateMover9000, moves, stacks)
+}
+
+fn run_part2(moves: &[Move], stacks: Stacks) -> Result<String, MoveError> {
+    run_crane(&mut CrateMover9001, moves, stacks)
+}
+
+/// Runs every move with the given crane, returning the crates which end up on top
+fn run_crane(
+    crane: &mut dyn Crane,
+    moves: &[Move],
+    mut stacks: Stacks,
+) -> Result<String, MoveError> {
+    stacks.run(crane, moves)?;
     Ok(stacks.read_tops())
 }
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 struct Stack(Vec<char>);
 
 impl Stack {
@@ -51,16 +123,14 @@ impl Stack {
         Self(items.into_iter().collect())
     }
 
-    fn pop(&mut self) -> Option<char> {
-        self.0.pop()
-    }
-
     fn push(&mut self, item: char) {
         self.0.push(item)
     }
 
-    fn pop_many(&mut self, count: u8) -> Vec<char> {
-        self.0.split_off(self.0.len() - (count as usize))
+    /// Removes the top count items, keeping their order, or None if there aren't enough
+    fn pop_many(&mut self, count: usize) -> Option<Vec<char>> {
+        let at = self.0.len().checked_sub(count)?;
+        Some(self.0.split_off(at))
     }
 
     fn push_many(&mut self, items: &[char]) {
@@ -80,17 +150,23 @@ impl Stack {
 
 #[derive(PartialEq, Eq, Debug)]
 struct Move {
-    from: u8,
-    to: u8,
+    from: usize,
+    to: usize,
     count: u8,
 }
 
 impl Move {
-    fn new(from: u8, to: u8, count: u8) -> Self {
+    fn new(from: usize, to: usize, count: u8) -> Self {
         Self { from, to, count }
     }
 }
 
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
+    }
+}
+
 impl FromStr for Move {
     type Err = Box<dyn Error>;
     fn from_str(s: &str) -> Result<Move, Self::Err> {
@@ -108,8 +184,8 @@ impl FromStr for Move {
         let to_str = &m[3];
 
         Ok(Move::new(
-            u8::from_str(from_str)?,
-            u8::from_str(to_str)?,
+            usize::from_str(from_str)?,
+            usize::from_str(to_str)?,
             u8::from_str(count_str)?,
         ))
     }
@@ -122,8 +198,16 @@ fn parse_input_instructions(instructions: &str) -> Result<Vec<Move>, Box<dyn Err
         .collect::<Result<Vec<Move>, _>>()
 }
 
-#[derive(PartialEq, Eq, Debug)]
-struct Stacks(HashMap<u8, Stack>);
+/// Splits the input into the drawing of the starting stacks and the moves after the blank line
+fn parse_input(input: &str) -> Result<(Stacks, Vec<Move>), Box<dyn Error>> {
+    let (drawing, instructions) = input
+        .split_once("\n\n")
+        .ok_or("Expected a blank line between the drawing and the moves")?;
+    Ok((drawing.parse()?, parse_input_instructions(instructions)?))
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct Stacks(HashMap<usize, Stack>);
 
 impl Stacks {
     fn new(stacks: impl IntoIterator<Item = Stack>) -> Self {
@@ -131,64 +215,286 @@ impl Stacks {
             stacks
                 .into_iter()
                 .enumerate()
-                .map(|(i, s)| ((i + 1) as u8, s))
+                .map(|(i, s)| (i + 1, s))
                 .collect(),
         )
     }
 
-    fn run_move(&mut self, m: &Move) -> bool {
-        for _ in 0..m.count {
-            if self.run_move_step(m).is_none() {
-                return false;
+    /// Checks every move can be made before making any of them, then runs them all with
+    /// the given crane
+    fn run(&mut self, crane: &mut dyn Crane, moves: &[Move]) -> Result<(), MoveError> {
+        self.validate(moves)?;
+        for m in moves {
+            crane.run_move(self, m);
+        }
+        Ok(())
+    }
+
+    /// Works out the height of each stack as the moves go along, since that doesn't
+    /// depend on the crane, to find the first move which refers to a stack that isn't
+    /// there or which takes more crates than a stack has
+    fn validate(&self, moves: &[Move]) -> Result<(), MoveError> {
+        let mut heights: HashMap<usize, usize> =
+            self.0.iter().map(|(id, s)| (*id, s.0.len())).collect();
+        for (index, m) in moves.iter().enumerate() {
+            for stack in [m.from, m.to] {
+                if !heights.contains_key(&stack) {
+                    return Err(MoveError::NoSuchStack { index, stack });
+                }
             }
+            let height = heights[&m.from];
+            if height < m.count as usize {
+                return Err(MoveError::Underflow {
+                    index,
+                    stack: m.from,
+                    height,
+                    count: m.count,
+                });
+            }
+            heights.insert(m.from, height - m.count as usize);
+            *heights.get_mut(&m.to).expect("checked above") += m.count as usize;
         }
-        true
+        Ok(())
     }
 
-    fn run_move_step(&mut self, m: &Move) -> Option<()> {
-        let removed = self.0.get_mut(&m.from)?.pop()?;
-        self.0.get_mut(&m.to)?.push(removed);
-        Some(())
+    /// Takes the top count crates off a stack, keeping their order. Only for moves
+    /// which have been validated.
+    fn take(&mut self, stack: usize, count: usize) -> Vec<char> {
+        self.0
+            .get_mut(&stack)
+            .and_then(|s| s.pop_many(count))
+            .expect("validated moves only take crates which are there")
     }
 
-    fn run_move_cratemover_9001(&mut self, m: &Move) -> Option<()> {
-        let removed = self.0.get_mut(&m.from)?.pop_many(m.count);
-        self.0.get_mut(&m.to)?.push_many(&removed);
-        Some(())
+    fn put(&mut self, stack: usize, crates: &[char]) {
+        self.0
+            .get_mut(&stack)
+            .expect("validated moves only use stacks which are there")
+            .push_many(crates)
     }
 
     fn read_tops(&self) -> String {
-        let mut indexes: Vec<u8> = self.0.keys().cloned().collect();
+        let mut indexes: Vec<usize> = self.0.keys().cloned().collect();
         indexes.sort();
         indexes
             .into_iter()
             .filter_map(|s| self.0[&s].peek_top())
             .collect()
     }
+
+    /// Every state the stacks go through as the crane runs the moves, starting with
+    /// how they are now
+    fn states(&self, crane: &mut dyn Crane, moves: &[Move]) -> Result<Vec<Stacks>, MoveError> {
+        self.validate(moves)?;
+        let mut stacks = self.clone();
+        let mut states = vec![stacks.clone()];
+        for m in moves {
+            crane.run_move(&mut stacks, m);
+            states.push(stacks.clone());
+        }
+        Ok(states)
+    }
+
+    fn height(&self) -> usize {
+        self.0.values().map(|s| s.0.len()).max().unwrap_or(0)
+    }
+
+    /// Draws the stacks like the top of the puzzle input, with blank rows on top to make
+    /// it at least min_height crates tall so drawings of different states line up
+    fn draw(&self, min_height: usize) -> String {
+        let mut ids: Vec<usize> = self.0.keys().copied().collect();
+        ids.sort();
+        let mut drawing = String::new();
+        for row in (0..self.height().max(min_height)).rev() {
+            let line = ids
+                .iter()
+                .map(|id| match self.0[id].0.get(row) {
+                    Some(item) => format!("[{}]", item),
+                    None => "   ".to_owned(),
+                })
+                .join(" ");
+            drawing.push_str(line.trim_end());
+            drawing.push('\n');
+        }
+        let numbers = ids.iter().map(|id| format!("{:^3}", id)).join(" ");
+        drawing.push_str(numbers.trim_end());
+        drawing.push('\n');
+        drawing
+    }
+}
+
+impl fmt::Display for Stacks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.draw(0))
+    }
+}
+
+/// Why a list of moves can't be run, naming the move by its 0-based index in the list
+#[derive(PartialEq, Eq, Debug)]
+enum MoveError {
+    NoSuchStack {
+        index: usize,
+        stack: usize,
+    },
+    Underflow {
+        index: usize,
+        stack: usize,
+        height: usize,
+        count: u8,
+    },
+}
+
+impl Error for MoveError {}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::NoSuchStack { index, stack } => {
+                write!(
+                    f,
+                    "Move at index {} uses stack {}, which doesn't exist",
+                    index, stack
+                )
+            }
+            MoveError::Underflow {
+                index,
+                stack,
+                height,
+                count,
+            } => write!(
+                f,
+                "Move at index {} takes {} crates from stack {}, which only has {} by then",
+                index, count, stack, height
+            ),
+        }
+    }
 }
 
-fn make_stack(s: &str) -> Stack {
-    Stack::new(s.chars())
+/// Something wrong with the drawing of the stacks, at a 1-based line and column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrawingError {
+    line: usize,
+    column: usize,
+    message: String,
 }
 
-/// Hardcoded because parsing it as specified looks like a pain I don't need
-fn stacks_input() -> Stacks {
-    Stacks::new(vec![
-        make_stack("NSDCVQT"),
-        make_stack("MFV"),
-        make_stack("FQWDPNHM"),
-        make_stack("DQRTF"),
-        make_stack("RFMNQHVB"),
-        make_stack("CFGNPWQ"),
-        make_stack("WFRLCT"),
-        make_stack("TZNS"),
-        make_stack("MSDJRQHN"),
-    ])
+impl Error for DrawingError {}
+
+impl fmt::Display for DrawingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
 }
 
-#[cfg(test)]
-fn stacks_test_input() -> Stacks {
-    Stacks::new(vec![make_stack("ZN"), make_stack("MCD"), make_stack("P")])
+impl FromStr for Stacks {
+    type Err = DrawingError;
+
+    /// Reads a drawing of the stacks like the one at the top of the puzzle input, with a
+    /// row of stack numbers under a crate like `[A]` in each stack's three character slot
+    /// on each line. Any number of stacks works, and lines can stop after the last crate.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s.lines().collect();
+        let (numbers, rows) = lines
+            .split_last()
+            .ok_or_else(|| drawing_error(1, 1, "The drawing is empty".to_owned()))?;
+
+        let count = parse_stack_numbers(numbers, lines.len())?;
+        let mut stacks = vec![Stack::new([]); count];
+
+        // read from the bottom up so each crate goes on top of the one under it
+        for (i, row) in rows.iter().enumerate().rev() {
+            let line = i + 1;
+            let height = rows.len() - i - 1;
+            for (index, column, slot) in slots(row, line)? {
+                let item = match slot.as_slice() {
+                    [' ', ' ', ' '] => continue,
+                    ['[', item, ']'] if item.is_ascii_alphabetic() => *item,
+                    _ => {
+                        return Err(drawing_error(
+                            line,
+                            column,
+                            format!(
+                                "Expected a crate like '[A]' or spaces, found '{}'",
+                                slot.iter().collect::<String>()
+                            ),
+                        ))
+                    }
+                };
+                let stack = stacks.get_mut(index).ok_or_else(|| {
+                    drawing_error(
+                        line,
+                        column,
+                        format!("Crate '{}' is not above any of the {} stacks", item, count),
+                    )
+                })?;
+                if stack.0.len() != height {
+                    return Err(drawing_error(
+                        line,
+                        column,
+                        format!("Crate '{}' has nothing underneath it", item),
+                    ));
+                }
+                stack.push(item);
+            }
+        }
+        Ok(Stacks::new(stacks))
+    }
+}
+
+/// Checks the row of stack numbers under the drawing counts up from 1, returning how many
+/// stacks there are
+fn parse_stack_numbers(numbers: &str, line: usize) -> Result<usize, DrawingError> {
+    let mut count = 0;
+    for (index, column, slot) in slots(numbers, line)? {
+        let label = slot.iter().collect::<String>();
+        match label.trim().parse::<usize>() {
+            Ok(n) if n == index + 1 => count = n,
+            _ => {
+                return Err(drawing_error(
+                    line,
+                    column,
+                    format!("Expected stack number {}, found '{}'", index + 1, label),
+                ))
+            }
+        }
+    }
+    if count == 0 {
+        return Err(drawing_error(
+            line,
+            1,
+            "Expected a row of stack numbers".to_owned(),
+        ));
+    }
+    Ok(count)
+}
+
+/// Splits a line of the drawing into the three character slots for each stack, giving
+/// the index of the stack and the 1-based column its slot starts at
+fn slots(text: &str, line: usize) -> Result<Vec<(usize, usize, Vec<char>)>, DrawingError> {
+    let chars: Vec<char> = text.trim_end().chars().collect();
+    chars
+        .chunks(4)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let column = index * 4 + 1;
+            match chunk.get(3) {
+                Some(' ') | None => Ok((index, column, chunk[..chunk.len().min(3)].to_vec())),
+                Some(c) => Err(drawing_error(
+                    line,
+                    column + 3,
+                    format!("Expected a space between stacks, found '{}'", c),
+                )),
+            }
+        })
+        .collect()
+}
+
+fn drawing_error(line: usize, column: usize, message: String) -> DrawingError {
+    DrawingError {
+        line,
+        column,
+        message,
+    }
 }
 
 #[test]
@@ -205,22 +511,86 @@ fn test_run_move() {
     let r#move = Move::new(2, 1, 2); // move two items from 2 to 1
     let mut stacks = Stacks::new(vec![stack1, stack2]);
 
-    stacks.run_move(&r#move);
+    stacks
+        .run(&mut CrateMover9000, &[r#move])
+        .expect("This move is valid");
 
     assert_eq!(stacks.0[&1].0, vec!('A', 'B', 'C', 'E', 'F'));
     assert_eq!(stacks.0[&2].0, vec!('D'));
 }
 
 #[test]
-fn test_part1_sample() {
-    let stacks = stacks_test_input();
-    let moves = parse_input_instructions(
-        "move 1 from 2 to 1
-move 3 from 1 to 3
-move 2 from 2 to 1
-move 1 from 1 to 2",
+fn test_moves_validated_up_front() {
+    let (stacks, mut moves) = parse_input(include_str!("inputs/day5-sample.txt")).unwrap();
+    moves.push(Move::new(2, 1, 2));
+    moves.push(Move::new(1, 4, 1));
+
+    // the sample leaves one crate on stack 2, so the fifth move can't take two from it
+    let mut run = stacks.clone();
+    assert_eq!(
+        run.run(&mut CrateMover9001, &moves),
+        Err(MoveError::Underflow {
+            index: 4,
+            stack: 2,
+            height: 1,
+            count: 2
+        })
+    );
+    assert_eq!(run, stacks, "Nothing should move if any move is invalid");
+
+    let error = stacks.validate(&moves[5..]).unwrap_err();
+    assert_eq!(error, MoveError::NoSuchStack { index: 0, stack: 4 });
+    assert_eq!(
+        error.to_string(),
+        "Move at index 0 uses stack 4, which doesn't exist"
+    );
+
+    let mut stack = Stack::new(['A']);
+    assert_eq!(stack.pop_many(2), None);
+    assert_eq!(stack.pop_many(1), Some(vec!['A']));
+}
+
+#[test]
+fn test_draw_stacks() {
+    let (stacks, _) = parse_input(include_str!("inputs/day5-sample.txt")).unwrap();
+    assert_eq!(
+        stacks.to_string(),
+        "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3\n"
+    );
+    assert_eq!(stacks.to_string().parse::<Stacks>().unwrap(), stacks);
+    assert_eq!(
+        Stacks::new([Stack::new(['A'])]).draw(2),
+        "\n[A]\n 1\n",
+        "Short stacks should be padded to line up"
+    );
+}
+
+#[test]
+fn test_play() {
+    let (stacks, moves) = parse_input(include_str!("inputs/day5-sample.txt")).unwrap();
+    let mut out = Vec::new();
+    play(
+        &mut out,
+        &mut CrateMover9000,
+        &moves,
+        &stacks,
+        Duration::ZERO,
     )
-    .expect("These moves should parse");
+    .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let frames: Vec<&str> = out.split("\x1b[2J\x1b[H").skip(1).collect();
+    assert_eq!(frames.len(), moves.len() + 1);
+    assert_eq!(
+        frames[1],
+        "Move 1 of 4: move 1 from 2 to 1\n\n\n[D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3\n"
+    );
+    assert!(out.ends_with("\nAfter 4 moves the CrateMover 9000 leaves CMZ on top\n"));
+}
+
+#[test]
+fn test_part1_sample() {
+    let (stacks, moves) =
+        parse_input(include_str!("inputs/day5-sample.txt")).expect("The sample should parse");
 
     let result = run_part1(&moves, stacks).expect("I expect success");
 
@@ -229,16 +599,74 @@ move 1 from 1 to 2",
 
 #[test]
 fn test_part2_sample() {
-    let stacks = stacks_test_input();
-    let moves = parse_input_instructions(
-        "move 1 from 2 to 1
-move 3 from 1 to 3
-move 2 from 2 to 1
-move 1 from 1 to 2",
-    )
-    .expect("These moves should parse");
+    let (stacks, moves) =
+        parse_input(include_str!("inputs/day5-sample.txt")).expect("The sample should parse");
 
     let result = run_part2(&moves, stacks).expect("I expect success");
 
     assert_eq!(&result, "MCD");
 }
+
+#[test]
+fn test_parse_drawing() {
+    let (stacks, _) = parse_input(include_str!("inputs/day5-sample.txt")).unwrap();
+    assert_eq!(
+        stacks,
+        Stacks::new(vec![
+            Stack::new("ZN".chars()),
+            Stack::new("MCD".chars()),
+            Stack::new("P".chars()),
+        ])
+    );
+
+    // ragged lines and more than nine stacks
+    let drawing = "                                        [K]
+[A]                                     [J]
+ 1   2   3   4   5   6   7   8   9  10  11";
+    let stacks: Stacks = drawing.parse().unwrap();
+    assert_eq!(stacks.0.len(), 11);
+    assert_eq!(stacks.read_tops(), "AK");
+}
+
+#[test]
+fn test_more_stacks_than_fit_in_a_byte() {
+    let crates = (1..=300)
+        .map(|i| if i == 300 { "[Z]" } else { "[A]" })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let numbers = (1..=300)
+        .map(|i| format!("{:^3}", i))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut stacks: Stacks = format!("{}\n{}", crates, numbers).parse().unwrap();
+    assert_eq!(stacks.0.len(), 300);
+    assert_eq!(stacks.0[&300].peek_top(), Some('Z'));
+
+    let moves = [Move::from_str("move 1 from 300 to 1").unwrap()];
+    stacks.run(&mut CrateMover9000, &moves).unwrap();
+    assert_eq!(stacks.0[&1].peek_top(), Some('Z'));
+    assert!(stacks.0[&300].peek_top().is_none());
+}
+
+#[test]
+fn test_drawing_errors() {
+    let error = |drawing: &str| drawing.parse::<Stacks>().unwrap_err().to_string();
+    assert_eq!(
+        error("[A] [B]\n 1"),
+        "1:5: Crate 'B' is not above any of the 1 stacks"
+    );
+    assert_eq!(
+        error("    [B]\n[A]\n 1   2"),
+        "1:5: Crate 'B' has nothing underneath it"
+    );
+    assert_eq!(
+        error("[A] (B)\n 1   2"),
+        "1:5: Expected a crate like '[A]' or spaces, found '(B)'"
+    );
+    assert_eq!(
+        error("[A]][B]\n 1   2"),
+        "1:4: Expected a space between stacks, found ']'"
+    );
+    assert_eq!(error("[A]\n 2"), "2:1: Expected stack number 1, found ' 2'");
+    assert_eq!(error("[A]\n"), "1:1: Expected stack number 1, found '[A]'");
+}
@@ -0,0 +1,117 @@
+use super::{Move, Stacks};
+
+/// A model of crane, which decides how the crates in a move are lifted and in what
+/// order they land. Moves are checked against the stacks before any crane runs them, so
+/// a crane can take as many crates as a move asks for.
+pub trait Crane {
+    fn name(&self) -> String;
+
+    fn run_move(&mut self, stacks: &mut Stacks, m: &Move);
+}
+
+/// Moves crates one at a time, so they land in the reverse order
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_owned()
+    }
+
+    fn run_move(&mut self, stacks: &mut Stacks, m: &Move) {
+        let mut crates = stacks.take(m.from, m.count as usize);
+        crates.reverse();
+        stacks.put(m.to, &crates);
+    }
+}
+
+/// Moves all the crates at once, so they keep their order
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_owned()
+    }
+
+    fn run_move(&mut self, stacks: &mut Stacks, m: &Move) {
+        let crates = stacks.take(m.from, m.count as usize);
+        stacks.put(m.to, &crates);
+    }
+}
+
+/// Moves up to a fixed number of crates at once, keeping the order of each lift
+pub struct LimitedCrane {
+    capacity: usize,
+}
+
+impl LimitedCrane {
+    pub fn new(capacity: usize) -> Result<Self, String> {
+        if capacity == 0 {
+            Err("A crane has to be able to lift at least one crate".to_owned())
+        } else {
+            Ok(Self { capacity })
+        }
+    }
+}
+
+impl Crane for LimitedCrane {
+    fn name(&self) -> String {
+        format!("crane lifting {} at a time", self.capacity)
+    }
+
+    fn run_move(&mut self, stacks: &mut Stacks, m: &Move) {
+        let mut remaining = m.count as usize;
+        while remaining > 0 {
+            let lift = remaining.min(self.capacity);
+            let crates = stacks.take(m.from, lift);
+            stacks.put(m.to, &crates);
+            remaining -= lift;
+        }
+    }
+}
+
+/// Moves all the crates at once, but turns every other batch upside down on the way,
+/// starting with the second
+#[derive(Default)]
+pub struct FlippingCrane {
+    flip_next: bool,
+}
+
+impl Crane for FlippingCrane {
+    fn name(&self) -> String {
+        "flipping crane".to_owned()
+    }
+
+    fn run_move(&mut self, stacks: &mut Stacks, m: &Move) {
+        let mut crates = stacks.take(m.from, m.count as usize);
+        if self.flip_next {
+            crates.reverse();
+        }
+        self.flip_next = !self.flip_next;
+        stacks.put(m.to, &crates);
+    }
+}
+
+#[cfg(test)]
+fn lift_three(crane: &mut dyn Crane) -> String {
+    let mut stacks: Stacks = "[A]\n[B]\n[C]\n 1   2".parse().unwrap();
+    crane.run_move(&mut stacks, &Move::new(1, 2, 3));
+    stacks.0[&2].0.iter().collect()
+}
+
+#[test]
+fn test_crane_orders() {
+    assert_eq!(lift_three(&mut CrateMover9000), "ABC");
+    assert_eq!(lift_three(&mut CrateMover9001), "CBA");
+    assert_eq!(lift_three(&mut LimitedCrane::new(2).unwrap()), "BAC");
+    assert_eq!(lift_three(&mut LimitedCrane::new(3).unwrap()), "CBA");
+    assert!(LimitedCrane::new(0).is_err());
+}
+
+#[test]
+fn test_flipping_crane_alternates() {
+    let mut stacks: Stacks = "[A] [C]\n[B] [D]\n 1   2   3".parse().unwrap();
+    let mut crane = FlippingCrane::default();
+    crane.run_move(&mut stacks, &Move::new(1, 3, 2));
+    crane.run_move(&mut stacks, &Move::new(2, 3, 2));
+    assert_eq!(stacks.0[&3].0.iter().collect::<String>(), "BACD");
+}
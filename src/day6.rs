@@ -1,4 +1,9 @@
 use crate::common::day;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    io::{self, BufReader, Read},
+};
 
 pub struct Day6 {
     input: &'static str,
@@ -14,8 +19,8 @@ impl Day6 {
 
 impl day::Day for Day6 {
     fn run(&mut self) -> day::Result {
-        let part1_result = run_part1(self.input.chars())?;
-        let part2_result = run_part2(self.input.chars())?;
+        let part1_result = run_part1(self.input.as_bytes())?;
+        let part2_result = run_part2(self.input.as_bytes())?;
         Ok((
             Some(format!("The first packet begins at {}", part1_result)),
             Some(format!("The first packet begins at {}", part2_result)),
@@ -23,67 +28,119 @@ impl day::Day for Day6 {
     }
 }
 
-fn run_part1(input: impl Iterator<Item = char> + Clone) -> Result<usize, String> {
-    find_packet_marker_of_size::<4>(input)
-        .ok_or_else(|| "Could not find a start of packet marker".to_owned())
+fn run_part1(input: impl Read) -> Result<usize, Box<dyn Error>> {
+    first_marker(input, 4)
 }
 
-fn run_part2(input: impl Iterator<Item = char> + Clone) -> Result<usize, String> {
-    find_packet_marker_of_size::<14>(input)
-        .ok_or_else(|| "Could not find a start of packet marker".to_owned())
+fn run_part2(input: impl Read) -> Result<usize, Box<dyn Error>> {
+    first_marker(input, 14)
 }
 
-fn find_packet_marker_of_size<const COUNT: usize>(
-    input: impl Iterator<Item = char> + Clone,
-) -> Option<usize> {
-    let mut last_n = ['\0'; COUNT];
-    // initialise initial buffer
-    for (i, c) in input.clone().take(COUNT).enumerate() {
-        last_n[i] = c;
-    }
-    if are_all_different(&last_n) {
-        return Some(COUNT);
+fn first_marker(input: impl Read, window: usize) -> Result<usize, Box<dyn Error>> {
+    markers(input, window)?
+        .next()
+        .ok_or("Could not find a start of packet marker")?
+        .map_err(|e| e.into())
+}
+
+/// Watches a stream of bytes a byte at a time for a window of bytes which are all
+/// different, keeping a count of each byte in the window so that each one takes the
+/// same amount of work however big the window is
+struct MarkerDetector {
+    window: usize,
+    recent: VecDeque<u8>,
+    counts: [usize; 256],
+    distinct: usize,
+    position: usize,
+}
+
+impl MarkerDetector {
+    fn new(window: usize) -> Result<Self, String> {
+        if window == 0 {
+            return Err("A marker has to be at least one byte long".to_owned());
+        }
+        Ok(Self {
+            window,
+            recent: VecDeque::with_capacity(window + 1),
+            counts: [0; 256],
+            distinct: 0,
+            position: 0,
+        })
     }
-    for (i, c) in input.skip(COUNT).enumerate() {
-        last_n.rotate_left(1);
-        last_n[COUNT - 1] = c;
-        if are_all_different(&last_n) {
-            return Some(i + 1 + COUNT);
+
+    /// Takes the next byte, returning how many bytes have been seen so far if it
+    /// completes a marker
+    fn push(&mut self, byte: u8) -> Option<usize> {
+        self.position += 1;
+        self.recent.push_back(byte);
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 1 {
+            self.distinct += 1;
+        }
+        if self.recent.len() > self.window {
+            let old = self.recent.pop_front().expect("the window isn't empty");
+            self.counts[old as usize] -= 1;
+            if self.counts[old as usize] == 0 {
+                self.distinct -= 1;
+            }
         }
+        (self.distinct == self.window).then_some(self.position)
     }
-    None
 }
 
-fn are_all_different<const COUNT: usize>(buffer: &[char; COUNT]) -> bool {
-    // probably a way to do this without allocating a new HashSet every time
-    use std::collections::HashSet;
-    let mut s = HashSet::new();
-    for c in buffer.iter() {
-        s.insert(c);
+/// The position just after every marker in the source, in order
+struct Markers<R> {
+    bytes: io::Bytes<R>,
+    detector: MarkerDetector,
+}
+
+impl<R: Read> Iterator for Markers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for byte in self.bytes.by_ref() {
+            match byte {
+                Ok(byte) => {
+                    if let Some(position) = self.detector.push(byte) {
+                        return Some(Ok(position));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        None
     }
-    s.len() == COUNT
+}
+
+/// Finds every marker of window different bytes in a row in the source, reading it
+/// only as far as needed to find each one
+fn markers<R: Read>(source: R, window: usize) -> Result<Markers<BufReader<R>>, String> {
+    Ok(Markers {
+        bytes: BufReader::new(source).bytes(),
+        detector: MarkerDetector::new(window)?,
+    })
 }
 
 #[test]
 fn test_part_one_samples() {
     assert_eq!(
-        find_packet_marker_of_size::<4>("mjqjpqmgbljsphdztnvjfqwrcgsmlb".chars()),
+        first_marker("mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes(), 4).ok(),
         Some(7)
     );
     assert_eq!(
-        find_packet_marker_of_size::<4>("bvwbjplbgvbhsrlpgdmjqwftvncz".chars()),
+        first_marker("bvwbjplbgvbhsrlpgdmjqwftvncz".as_bytes(), 4).ok(),
         Some(5)
     );
     assert_eq!(
-        find_packet_marker_of_size::<4>("nppdvjthqldpwncqszvftbrmjlhg".chars()),
+        first_marker("nppdvjthqldpwncqszvftbrmjlhg".as_bytes(), 4).ok(),
         Some(6)
     );
     assert_eq!(
-        find_packet_marker_of_size::<4>("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".chars()),
+        first_marker("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".as_bytes(), 4).ok(),
         Some(10)
     );
     assert_eq!(
-        find_packet_marker_of_size::<4>("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".chars()),
+        first_marker("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".as_bytes(), 4).ok(),
         Some(11)
     );
 }
@@ -91,23 +148,54 @@ fn test_part_one_samples() {
 #[test]
 fn test_part_two_samples() {
     assert_eq!(
-        find_packet_marker_of_size::<14>("mjqjpqmgbljsphdztnvjfqwrcgsmlb".chars()),
+        first_marker("mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes(), 14).ok(),
         Some(19)
     );
     assert_eq!(
-        find_packet_marker_of_size::<14>("bvwbjplbgvbhsrlpgdmjqwftvncz".chars()),
+        first_marker("bvwbjplbgvbhsrlpgdmjqwftvncz".as_bytes(), 14).ok(),
         Some(23)
     );
     assert_eq!(
-        find_packet_marker_of_size::<14>("nppdvjthqldpwncqszvftbrmjlhg".chars()),
+        first_marker("nppdvjthqldpwncqszvftbrmjlhg".as_bytes(), 14).ok(),
         Some(23)
     );
     assert_eq!(
-        find_packet_marker_of_size::<14>("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".chars()),
+        first_marker("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".as_bytes(), 14).ok(),
         Some(29)
     );
     assert_eq!(
-        find_packet_marker_of_size::<14>("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".chars()),
+        first_marker("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".as_bytes(), 14).ok(),
         Some(26)
     );
 }
+
+#[test]
+fn test_every_marker() {
+    let found = markers("abcabcaabc".as_bytes(), 3)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(found, vec![3, 4, 5, 6, 7, 10]);
+    assert_eq!(markers("abc".as_bytes(), 4).unwrap().count(), 0);
+    assert!(markers("abc".as_bytes(), 0).is_err());
+}
+
+#[test]
+fn test_reads_any_source() {
+    // hands over one byte per read, to check nothing depends on how the input arrives
+    struct Trickle<'a>(&'a [u8]);
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((byte, rest)), Some(out)) => {
+                    *out = *byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+    let input = Trickle(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb");
+    assert_eq!(first_marker(input, 14).unwrap(), 19);
+}
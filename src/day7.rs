@@ -1,8 +1,12 @@
-use std::{error::Error, fmt::Display, iter, str::FromStr};
+mod export;
+mod filesystem;
+mod transcript;
 
-use regex::Regex;
-
-use crate::common::day;
+use self::export::{to_json, to_svg};
+use self::filesystem::{Directory, Filesystem, Path};
+use self::transcript::{parse_input, replay};
+use crate::common::{day, image::Colour};
+use std::error::Error;
 
 pub struct Day7 {
     input: &'static str,
@@ -16,12 +20,23 @@ impl Day7 {
     }
 }
 
+impl Day7 {
+    /// The filesystem the input describes, warning about anything inconsistent in it
+    fn filesystem(&self) -> Result<Filesystem, Box<dyn Error>> {
+        let replay = replay(&parse_input(self.input)?);
+        for inconsistency in &replay.inconsistencies {
+            log::warn!("{}", inconsistency);
+        }
+        Ok(replay.filesystem)
+    }
+}
+
 impl day::Day for Day7 {
     fn run(&mut self) -> day::Result {
-        let instructions = parse_input(self.input)?;
-        let tree = build_directory_tree(&instructions)?;
-        let part1_result = run_part1(&tree);
-        let part2_result = run_part2(&tree);
+        let filesystem = self.filesystem()?;
+        let tree = filesystem.root();
+        let part1_result = run_part1(tree);
+        let part2_result = run_part2(tree);
         Ok((
             Some(part1_result.to_string()),
             Some(part2_result.to_string()),
@@ -29,241 +44,115 @@ impl day::Day for Day7 {
     }
 }
 
-fn run_part1(tree: &Directory) -> usize {
-    tree.find_directories(|d| d.total_size() <= 100000)
-        .map(|d| d.total_size())
-        .sum()
-}
-
-fn run_part2(tree: &Directory) -> usize {
-    let total_space = 70000000;
-    let required_space = 30000000;
-    let current_used_space = tree.total_size();
-    let current_available_space = total_space - current_used_space;
-    let must_delete_space = required_space - current_available_space;
-    let mut candidates = tree
-        .find_directories(|d| d.total_size() >= must_delete_space)
-        .collect::<Vec<_>>();
-    candidates.sort_by_key(|a| a.total_size());
-    candidates[0].total_size()
-}
-
-#[derive(PartialEq, Eq, Debug)]
-enum Command<'a> {
-    CDIn(&'a str),
-    CDOut,
-    CDRoot,
-    List,
+/// Answers a question about the filesystem in the puzzle input, asked like the shell
+/// command: `du [PATH]`, `find [PATH] -size N` or `tree [PATH]`. `json [PATH]` and
+/// `svg [PATH]` export the tree instead, with the SVG treemap showing which directories
+/// are big enough to free up the space for the update and which one part 2 picks.
+pub fn query(words: &[&str]) -> Result<String, Box<dyn Error>> {
+    run_query(&Day7::new().filesystem()?, words)
 }
 
-#[derive(PartialEq, Eq, Debug)]
-enum DirEntry<'a> {
-    /// Directory with name
-    Directory(&'a str),
-    /// File with name and size
-    File(&'a str, usize),
-}
-
-#[derive(PartialEq, Eq, Debug)]
-enum InputLine<'a> {
-    Command(Command<'a>),
-    Result(DirEntry<'a>),
-}
-
-fn parse_input_line(line: &str) -> Result<InputLine<'_>, Box<dyn Error>> {
-    lazy_static! {
-        static ref FILE_REGEX: Regex = Regex::new(r"^(\d+) (.+)$").unwrap();
-    }
-
-    let line = line.trim();
-    if line == "$ cd /" {
-        Ok(InputLine::Command(Command::CDRoot))
-    } else if line == "$ ls" {
-        Ok(InputLine::Command(Command::List))
-    } else if line == "$ cd .." {
-        Ok(InputLine::Command(Command::CDOut))
-    } else if let Some(dirname) = line.strip_prefix("$ cd ") {
-        Ok(InputLine::Command(Command::CDIn(dirname)))
-    } else if let Some(dirname) = line.strip_prefix("dir ") {
-        Ok(InputLine::Result(DirEntry::Directory(dirname)))
-    } else {
-        let mut parts = line.split_whitespace();
-        let size = parts
-            .next()
-            .ok_or_else(|| format!("Input line '{}' could not be parsed", line))?;
-        let name = parts.next().ok_or_else(|| {
-            format!(
-                "Input line '{}' could not be parsed: expecting file entry, but no second part",
-                line
-            )
-        })?;
-        let size = usize::from_str(size)?;
-        Ok(InputLine::Result(DirEntry::File(name, size)))
-    }
-}
-
-fn parse_input(input: &str) -> Result<Vec<InputLine<'_>>, Box<dyn Error>> {
-    input.lines().map(parse_input_line).collect()
-}
-
-#[derive(PartialEq, Eq, Debug)]
-struct Directory<'a> {
-    name: &'a str,
-    files: Vec<File<'a>>,
-    directories: Vec<Directory<'a>>,
-}
+fn run_query(filesystem: &Filesystem, words: &[&str]) -> Result<String, Box<dyn Error>> {
+    let path = |words: &[&str]| match words {
+        [] => Ok(Path::root()),
+        [path] => Path::root().resolve(path),
+        _ => Err(format!("Expected one path but got {}", words.join(" "))),
+    };
+    let not_found = |path: Path| format!("{} is not a directory", path);
+    let list = |entries: Vec<(Path, usize)>| {
+        entries
+            .into_iter()
+            .map(|(path, size)| format!("{}\t{}\n", size, path))
+            .collect()
+    };
 
-impl<'a> Directory<'a> {
-    fn new_empty(name: &'a str) -> Directory<'a> {
-        Directory {
-            name,
-            files: Vec::new(),
-            directories: Vec::new(),
+    match words {
+        ["du", rest @ ..] => {
+            let path = path(rest)?;
+            Ok(list(filesystem.du(&path).ok_or_else(|| not_found(path))?))
         }
-    }
-
-    fn add_subdirectory(&mut self, name: &'a str) -> bool {
-        if !self.knows_directory(name) {
-            self.directories.push(Directory::new_empty(name));
-            true
-        } else {
-            false
+        ["find", rest @ .., "-size", size] => {
+            let path = path(rest)?;
+            let found = filesystem.find_size(&path, size.parse()?);
+            Ok(list(found.ok_or_else(|| not_found(path))?))
         }
-    }
-
-    fn knows_directory(&self, name: &'a str) -> bool {
-        self.directories.iter().any(|d| d.name == name)
-    }
-
-    fn add_file(&mut self, name: &'a str, size: usize) {
-        self.files.push(File::new(name, size))
-    }
-
-    fn find_directory_mut(&mut self, path: &[&'a str]) -> Option<&mut Directory<'a>> {
-        let mut current = self;
-        for p in path {
-            current = current.get_directory_mut(p)?;
+        ["tree", rest @ ..] => {
+            let path = path(rest)?;
+            Ok(filesystem.tree(&path).ok_or_else(|| not_found(path))?)
         }
-        Some(current)
-    }
-
-    fn get_directory_mut(&mut self, name: &'a str) -> Option<&mut Directory<'a>> {
-        self.directories.iter_mut().find(|d| d.name == name)
-    }
-
-    fn total_size(&self) -> usize {
-        self.files.iter().map(|f| f.size).sum::<usize>()
-            + self
-                .directories
-                .iter()
-                .map(|d| d.total_size())
-                .sum::<usize>()
-    }
-
-    fn find_directories(
-        &'a self,
-        f: impl Fn(&Self) -> bool + 'a,
-    ) -> impl Iterator<Item = &Directory> {
-        self.iterate_directories().filter(move |d| f(d))
-    }
-
-    fn iterate_directories(&'a self) -> impl Iterator<Item = &Directory> {
-        self.directories.iter().flat_map(|d| {
-            iter::once(d)
-                .chain(d.iterate_directories())
-                .collect::<Vec<_>>() // this gets around a recursive opaque type issue - appears to be a language limitation or me being silly
-        })
-    }
-}
-
-impl<'a> Display for Directory<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "{} (dir)", self.name)?;
-        for d in self.directories.iter() {
-            writeln!(f, "{}", d)?;
+        ["json", rest @ ..] => {
+            let path = path(rest)?;
+            to_json(filesystem.directory(&path).ok_or_else(|| not_found(path))?)
         }
-        for file in self.files.iter() {
-            writeln!(f, "{} ({} bytes)", file.name, file.size)?;
+        ["svg", rest @ ..] => {
+            let path = path(rest)?;
+            let directory = filesystem
+                .directory(&path)
+                .ok_or_else(|| not_found(path.clone()))?;
+            let must_delete = must_delete_space(filesystem.root());
+            let chosen = directory_to_delete(filesystem);
+            let svg = to_svg(directory, &path, 1200.0, 800.0, |p, d| {
+                if Some(p) == chosen.as_ref() {
+                    CHOSEN_COLOUR
+                } else if *p != Path::root() && d.total_size() >= must_delete {
+                    CANDIDATE_COLOUR
+                } else {
+                    DIRECTORY_COLOUR
+                }
+            });
+            Ok(svg)
         }
-        Ok(())
+        _ => Err(
+            "Expected du [PATH], find [PATH] -size N, tree [PATH], json [PATH] or svg [PATH]"
+                .into(),
+        ),
     }
 }
 
-#[derive(PartialEq, Eq, Debug)]
-struct File<'a> {
-    name: &'a str,
-    size: usize,
+const DIRECTORY_COLOUR: Colour = Colour::grey(235);
+const CANDIDATE_COLOUR: Colour = Colour::new(250, 200, 120);
+const CHOSEN_COLOUR: Colour = Colour::new(230, 80, 60);
+
+/// The path of the directory run_part2 finds, for highlighting it
+fn directory_to_delete(filesystem: &Filesystem) -> Option<Path> {
+    let must_delete = must_delete_space(filesystem.root());
+    filesystem
+        .du(&Path::root())?
+        .into_iter()
+        .filter(|(path, size)| *path != Path::root() && *size >= must_delete)
+        .min_by_key(|(_, size)| *size)
+        .map(|(path, _)| path)
 }
 
-impl<'a> File<'a> {
-    fn new(name: &'a str, size: usize) -> File<'a> {
-        File { name, size }
-    }
+fn run_part1(tree: &Directory) -> usize {
+    tree.find_directories(|d| d.total_size() <= 100000)
+        .map(|d| d.total_size())
+        .sum()
 }
 
-fn build_directory_tree<'a>(input: &[InputLine<'a>]) -> Result<Directory<'a>, Box<dyn Error>> {
-    let mut root = Directory {
-        name: "/",
-        files: Vec::new(),
-        directories: Vec::new(),
-    };
-
-    let mut current_path = Vec::new();
-
-    for i in input {
-        match i {
-            InputLine::Result(DirEntry::File(name, size)) => {
-                root.find_directory_mut(&current_path)
-                    .ok_or_else(|| format!("Unable to find directory {:?}", current_path))?
-                    .add_file(name, *size);
-            }
-            InputLine::Result(DirEntry::Directory(name)) => {
-                let result = root
-                    .find_directory_mut(&current_path)
-                    .ok_or_else(|| format!("Unable to find directory {:?}", current_path))?
-                    .add_subdirectory(name);
-                if !result {
-                    return Err(format!(
-                        "Unable to add directory {} as there already was one",
-                        name
-                    )
-                    .into());
-                }
-            }
-            InputLine::Command(Command::CDIn(name)) => current_path.push(name),
-            InputLine::Command(Command::CDOut) => {
-                current_path.pop().ok_or_else(|| {
-                    "Unable to cd .. because already at the root directory".to_string()
-                })?;
-            }
-            InputLine::Command(Command::CDRoot) => current_path.clear(),
-            InputLine::Command(Command::List) => {}
-        }
-    }
+/// How much has to be deleted to leave enough space for the update
+fn must_delete_space(tree: &Directory) -> usize {
+    let total_space = 70000000;
+    let required_space: usize = 30000000;
+    let current_used_space = tree.total_size();
+    let current_available_space = total_space - current_used_space;
+    required_space.saturating_sub(current_available_space)
+}
 
-    Ok(root)
+fn run_part2(tree: &Directory) -> usize {
+    let must_delete_space = must_delete_space(tree);
+    let mut candidates = tree
+        .find_directories(|d| d.total_size() >= must_delete_space)
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|a| a.total_size());
+    candidates[0].total_size()
 }
 
-#[test]
-fn test_parse_input() {
-    let input = "$ cd /
-$ ls
-dir a
-34 a.txt
-$ cd a
-$ cd ..";
-    let parsed = parse_input(input).expect("Should parse");
-    assert_eq!(
-        parsed,
-        vec![
-            InputLine::Command(Command::CDRoot),
-            InputLine::Command(Command::List),
-            InputLine::Result(DirEntry::Directory("a")),
-            InputLine::Result(DirEntry::File("a.txt", 34)),
-            InputLine::Command(Command::CDIn("a")),
-            InputLine::Command(Command::CDOut)
-        ]
-    );
+#[cfg(test)]
+fn build_directory_tree(input: &[transcript::InputLine]) -> Directory {
+    let replay = replay(input);
+    assert_eq!(replay.inconsistencies, vec![]);
+    replay.filesystem.root().clone()
 }
 
 #[test]
@@ -278,7 +167,7 @@ $ ls
     )
     .expect("Should parse");
 
-    let dir = build_directory_tree(&input).expect("shouldn't crash");
+    let dir = build_directory_tree(&input);
 
     assert_eq!(dir.name, "/");
     assert_eq!(dir.files, vec![]);
@@ -303,7 +192,7 @@ $ ls
     )
     .expect("Should parse");
 
-    let dir = build_directory_tree(&input).expect("shouldn't crash");
+    let dir = build_directory_tree(&input);
 
     assert_eq!(dir.total_size(), 228);
 }
@@ -324,7 +213,7 @@ $ ls
     )
     .expect("Should parse");
 
-    let dir = build_directory_tree(&input).expect("shouldn't crash");
+    let dir = build_directory_tree(&input);
 
     assert_eq!(dir.total_size(), 230);
     assert_eq!(dir.directories[0].directories[0].total_size(), 2);
@@ -356,10 +245,8 @@ $ ls
 7214296 k";
 
 #[cfg(test)]
-fn build_sample_input_tree() -> Directory<'static> {
-    let tree = build_directory_tree(&parse_input(INPUT).expect("Input should parse"))
-        .expect("Tree should build");
-    tree
+fn build_sample_input_tree() -> Directory {
+    build_directory_tree(&parse_input(INPUT).expect("Input should parse"))
 }
 
 #[test]
@@ -375,3 +262,51 @@ fn test_part2_sample() {
     let result = run_part2(&tree);
     assert_eq!(result, 24933642);
 }
+
+#[test]
+fn test_queries() {
+    let replay = replay(&parse_input(INPUT).unwrap());
+    let query = |words: &str| {
+        run_query(
+            &replay.filesystem,
+            &words.split_whitespace().collect::<Vec<_>>(),
+        )
+    };
+    assert_eq!(
+        query("du /a").unwrap(),
+        "584\t/a/e\n94853\t/a\n",
+        "du should list children before parents"
+    );
+    assert_eq!(
+        query("find -size +8M").unwrap(),
+        "14848514\t/b.txt\n8504156\t/c.dat\n"
+    );
+    assert_eq!(query("find d -size -5000k").unwrap(), "4060174\t/d/j\n");
+    assert_eq!(query("tree a/e").unwrap(), "/a/e (dir, 584)\n└── i (584)\n");
+    assert!(query("du /nowhere").is_err());
+    assert!(query("ls").is_err());
+}
+
+#[test]
+fn test_exports() {
+    let replay = replay(&parse_input(INPUT).unwrap());
+    assert_eq!(
+        directory_to_delete(&replay.filesystem),
+        Some(Path::root().join("d"))
+    );
+
+    let json = run_query(&replay.filesystem, &["json", "a"]).unwrap();
+    assert!(json.contains("\"size\": 94853"));
+
+    let svg = run_query(&replay.filesystem, &["svg"]).unwrap();
+    let colour_of = |title: &str| {
+        let line = svg
+            .lines()
+            .find(|l| l.contains(&format!("<title>{} (", title)))
+            .unwrap();
+        line.split("fill=\"").nth(1).unwrap()[..7].to_owned()
+    };
+    assert_eq!(colour_of("/d"), CHOSEN_COLOUR.hex());
+    assert_eq!(colour_of("/a"), DIRECTORY_COLOUR.hex());
+    assert_eq!(colour_of("/"), DIRECTORY_COLOUR.hex());
+}
@@ -0,0 +1,229 @@
+use super::filesystem::{Directory, Path};
+use crate::common::{image::Colour, serialise};
+use serde::Serialize;
+use std::{error::Error, fmt::Write};
+
+/// A directory or file as it's written out to JSON. Only directories have children.
+#[derive(Serialize, PartialEq, Eq, Debug)]
+pub struct Node {
+    pub name: String,
+    pub size: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<Node>>,
+}
+
+impl From<&Directory> for Node {
+    fn from(directory: &Directory) -> Self {
+        let children = directory
+            .directories
+            .iter()
+            .map(Node::from)
+            .chain(directory.files.iter().map(|f| Node {
+                name: f.name.clone(),
+                size: f.size,
+                children: None,
+            }))
+            .collect();
+        Node {
+            name: directory.name.clone(),
+            size: directory.total_size(),
+            children: Some(children),
+        }
+    }
+}
+
+pub fn to_json(directory: &Directory) -> Result<String, Box<dyn Error>> {
+    serialise::to_text(&Node::from(directory))
+}
+
+const FILE_COLOUR: Colour = Colour::new(160, 190, 230);
+const PADDING: f64 = 2.0;
+const LABEL_HEIGHT: f64 = 12.0;
+/// Roughly how wide each character of a label is, to decide whether it fits
+const CHARACTER_WIDTH: f64 = 6.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+/// Draws the directory at path as a treemap, where each directory and file gets an area
+/// in proportion to its size. Each directory is filled with the colour chosen for it, and
+/// hovering over anything shows its full path and size.
+pub fn to_svg(
+    directory: &Directory,
+    path: &Path,
+    width: f64,
+    height: f64,
+    fill: impl Fn(&Path, &Directory) -> Colour,
+) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"10\">\n",
+        w = width,
+        h = height
+    );
+    let rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        width,
+        height,
+    };
+    draw_directory(&mut svg, directory, path, rect, &fill);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn draw_directory(
+    svg: &mut String,
+    directory: &Directory,
+    path: &Path,
+    rect: Rect,
+    fill: &impl Fn(&Path, &Directory) -> Colour,
+) {
+    draw_box(
+        svg,
+        rect,
+        fill(path, directory),
+        &path.to_string(),
+        directory.total_size(),
+    );
+
+    // leave room for the directory's own label above its contents if there's space
+    let top = if rect.height > LABEL_HEIGHT * 2.0 {
+        LABEL_HEIGHT
+    } else {
+        PADDING
+    };
+    let inner = Rect {
+        x: rect.x + PADDING,
+        y: rect.y + top,
+        width: rect.width - PADDING * 2.0,
+        height: rect.height - top - PADDING,
+    };
+    let total = directory.total_size();
+    if total == 0 || inner.width < 1.0 || inner.height < 1.0 {
+        return;
+    }
+
+    // lay the contents out side by side along the longer side
+    let horizontal = inner.width >= inner.height;
+    let mut offset = 0.0;
+    let mut next = |size: usize| {
+        let share = size as f64 / total as f64;
+        let rect = if horizontal {
+            Rect {
+                x: inner.x + offset * inner.width,
+                width: share * inner.width,
+                ..inner
+            }
+        } else {
+            Rect {
+                y: inner.y + offset * inner.height,
+                height: share * inner.height,
+                ..inner
+            }
+        };
+        offset += share;
+        rect
+    };
+    for d in &directory.directories {
+        let rect = next(d.total_size());
+        draw_directory(svg, d, &path.join(&d.name), rect, fill);
+    }
+    for f in &directory.files {
+        let rect = next(f.size);
+        draw_box(
+            svg,
+            rect,
+            FILE_COLOUR,
+            &path.join(&f.name).to_string(),
+            f.size,
+        );
+    }
+}
+
+fn draw_box(svg: &mut String, rect: Rect, colour: Colour, title: &str, size: usize) {
+    if rect.width <= 0.0 || rect.height <= 0.0 {
+        return;
+    }
+    let _ = writeln!(
+        svg,
+        "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\" \
+         stroke=\"black\" stroke-width=\"0.5\"><title>{} ({})</title></rect>",
+        rect.x,
+        rect.y,
+        rect.width,
+        rect.height,
+        colour.hex(),
+        escape(title),
+        size
+    );
+    let name = title.rsplit('/').find(|s| !s.is_empty()).unwrap_or("/");
+    if rect.height >= LABEL_HEIGHT && rect.width >= name.len() as f64 * CHARACTER_WIDTH {
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+            rect.x + PADDING,
+            rect.y + LABEL_HEIGHT - PADDING,
+            escape(name)
+        );
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+fn example() -> Directory {
+    let mut root = Directory::new_empty("/");
+    let mut a = Directory::new_empty("a");
+    a.files.push(super::filesystem::File::new("x<y", 30));
+    root.directories.push(a);
+    root.files.push(super::filesystem::File::new("b", 10));
+    root
+}
+
+#[test]
+fn test_json() {
+    let json: serde_json::Value = serde_json::from_str(&to_json(&example()).unwrap()).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "name": "/",
+            "size": 40,
+            "children": [
+                { "name": "a", "size": 30, "children": [{ "name": "x<y", "size": 30 }] },
+                { "name": "b", "size": 10 }
+            ]
+        })
+    );
+}
+
+#[test]
+fn test_svg_treemap() {
+    let svg = to_svg(&example(), &Path::root(), 400.0, 100.0, |p, _| {
+        if p.name() == "a" {
+            Colour::new(255, 0, 0)
+        } else {
+            Colour::WHITE
+        }
+    });
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.ends_with("</svg>\n"));
+    // a gets three quarters of the width inside the root's padding, and b the rest
+    assert!(svg.contains(
+        "<rect x=\"2.0\" y=\"12.0\" width=\"297.0\" height=\"86.0\" fill=\"#ff0000\" \
+         stroke=\"black\" stroke-width=\"0.5\"><title>/a (30)</title></rect>"
+    ));
+    assert!(svg.contains("<title>/b (10)</title>"));
+    assert!(svg.contains("<title>/a/x&lt;y (30)</title>"));
+    assert!(svg.contains(">x&lt;y</text>"));
+}
@@ -0,0 +1,444 @@
+use std::{
+    fmt::{self, Display},
+    iter,
+    str::FromStr,
+};
+
+/// An absolute path, as the names of each directory on the way down from the root
+#[derive(PartialEq, Eq, Debug, Clone, Default, Hash, PartialOrd, Ord)]
+pub struct Path(Vec<String>);
+
+impl Path {
+    pub fn root() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Works out where a path leads from this one, the way `cd` would. Paths starting
+    /// with `/` are absolute, and `..` goes up to the parent, which can't be done from
+    /// the root.
+    pub fn resolve(&self, path: &str) -> Result<Path, String> {
+        let mut components = if path.starts_with('/') {
+            Vec::new()
+        } else {
+            self.0.clone()
+        };
+        for part in path.split('/') {
+            match part {
+                "" | "." => {}
+                ".." => {
+                    components
+                        .pop()
+                        .ok_or_else(|| format!("'{}' goes above the root from {}", path, self))?;
+                }
+                name => components.push(name.to_owned()),
+            }
+        }
+        Ok(Path(components))
+    }
+
+    pub fn join(&self, name: &str) -> Path {
+        let mut components = self.0.clone();
+        components.push(name.to_owned());
+        Path(components)
+    }
+
+    pub fn parent(&self) -> Option<Path> {
+        self.0.split_last().map(|(_, rest)| Path(rest.to_vec()))
+    }
+
+    /// The last part of the path, or `/` for the root
+    pub fn name(&self) -> &str {
+        self.0.last().map(String::as_str).unwrap_or("/")
+    }
+
+    pub fn components(&self) -> &[String] {
+        &self.0
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "/");
+        }
+        for c in &self.0 {
+            write!(f, "/{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Directory {
+    pub name: String,
+    pub files: Vec<File>,
+    pub directories: Vec<Directory>,
+}
+
+impl Directory {
+    pub fn new_empty(name: &str) -> Directory {
+        Directory {
+            name: name.to_owned(),
+            files: Vec::new(),
+            directories: Vec::new(),
+        }
+    }
+
+    pub fn directory(&self, name: &str) -> Option<&Directory> {
+        self.directories.iter().find(|d| d.name == name)
+    }
+
+    fn directory_mut(&mut self, name: &str) -> Option<&mut Directory> {
+        self.directories.iter_mut().find(|d| d.name == name)
+    }
+
+    pub fn file(&self, name: &str) -> Option<&File> {
+        self.files.iter().find(|f| f.name == name)
+    }
+
+    pub fn total_size(&self) -> usize {
+        self.files.iter().map(|f| f.size).sum::<usize>()
+            + self
+                .directories
+                .iter()
+                .map(|d| d.total_size())
+                .sum::<usize>()
+    }
+
+    pub fn find_directories<'a>(
+        &'a self,
+        f: impl Fn(&Self) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a Directory> {
+        self.iterate_directories().filter(move |d| f(d))
+    }
+
+    pub fn iterate_directories(&self) -> impl Iterator<Item = &Directory> {
+        self.directories.iter().flat_map(|d| {
+            iter::once(d)
+                .chain(d.iterate_directories())
+                .collect::<Vec<_>>() // this gets around a recursive opaque type issue - appears to be a language limitation or me being silly
+        })
+    }
+
+    /// Adds the total size of every directory from this one down to sizes, children
+    /// before their parents like du, returning this one's total
+    fn du(&self, path: &Path, sizes: &mut Vec<(Path, usize)>) -> usize {
+        let mut total = self.files.iter().map(|f| f.size).sum::<usize>();
+        for d in &self.directories {
+            total += d.du(&path.join(&d.name), sizes);
+        }
+        sizes.push((path.clone(), total));
+        total
+    }
+
+    fn find_files(&self, path: &Path, filter: SizeFilter, found: &mut Vec<(Path, usize)>) {
+        for f in &self.files {
+            if filter.matches(f.size) {
+                found.push((path.join(&f.name), f.size));
+            }
+        }
+        for d in &self.directories {
+            d.find_files(&path.join(&d.name), filter, found);
+        }
+    }
+
+    fn draw_tree(&self, prefix: &str, out: &mut String) {
+        let entries = self
+            .directories
+            .iter()
+            .map(|d| (Some(d), format!("{} (dir, {})", d.name, d.total_size())))
+            .chain(
+                self.files
+                    .iter()
+                    .map(|f| (None, format!("{} ({})", f.name, f.size))),
+            )
+            .collect::<Vec<_>>();
+        for (i, (directory, line)) in entries.iter().enumerate() {
+            let last = i == entries.len() - 1;
+            out.push_str(prefix);
+            out.push_str(if last { "└── " } else { "├── " });
+            out.push_str(line);
+            out.push('\n');
+            if let Some(d) = directory {
+                d.draw_tree(
+                    &format!("{}{}", prefix, if last { "    " } else { "│   " }),
+                    out,
+                );
+            }
+        }
+    }
+}
+
+impl Display for Directory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} (dir)", self.name)?;
+        for d in self.directories.iter() {
+            writeln!(f, "{}", d)?;
+        }
+        for file in self.files.iter() {
+            writeln!(f, "{} ({} bytes)", file.name, file.size)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct File {
+    pub name: String,
+    pub size: usize,
+}
+
+impl File {
+    pub fn new(name: &str, size: usize) -> File {
+        File {
+            name: name.to_owned(),
+            size,
+        }
+    }
+}
+
+/// Which file sizes to look for, written like the argument to `find -size`: `+N` for
+/// more than N, `-N` for less than N, or just `N` for exactly N. N is in bytes, unless
+/// it ends in `k`, `M` or `G` for multiples of 1024.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SizeFilter {
+    Over(usize),
+    Under(usize),
+    Exactly(usize),
+}
+
+impl SizeFilter {
+    pub fn matches(&self, size: usize) -> bool {
+        match self {
+            SizeFilter::Over(n) => size > *n,
+            SizeFilter::Under(n) => size < *n,
+            SizeFilter::Exactly(n) => size == *n,
+        }
+    }
+}
+
+impl FromStr for SizeFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (filter, amount): (fn(usize) -> SizeFilter, &str) = match s.as_bytes().first() {
+            Some(b'+') => (SizeFilter::Over, &s[1..]),
+            Some(b'-') => (SizeFilter::Under, &s[1..]),
+            _ => (SizeFilter::Exactly, s),
+        };
+        let (number, unit) = match amount.char_indices().last() {
+            Some((i, 'c')) => (&amount[..i], 1),
+            Some((i, 'k')) => (&amount[..i], 1 << 10),
+            Some((i, 'M')) => (&amount[..i], 1 << 20),
+            Some((i, 'G')) => (&amount[..i], 1 << 30),
+            _ => (amount, 1),
+        };
+        number
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_mul(unit))
+            .map(filter)
+            .ok_or_else(|| format!("'{}' is not a size like +100k, -20 or 512", s))
+    }
+}
+
+/// A tree of directories and files which can be built up a path at a time and queried
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Filesystem {
+    root: Directory,
+}
+
+impl Default for Filesystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Filesystem {
+    pub fn new() -> Self {
+        Self {
+            root: Directory::new_empty("/"),
+        }
+    }
+
+    pub fn root(&self) -> &Directory {
+        &self.root
+    }
+
+    pub fn directory(&self, path: &Path) -> Option<&Directory> {
+        let mut current = &self.root;
+        for name in path.components() {
+            current = current.directory(name)?;
+        }
+        Some(current)
+    }
+
+    fn directory_mut(&mut self, path: &Path) -> Option<&mut Directory> {
+        let mut current = &mut self.root;
+        for name in path.components() {
+            current = current.directory_mut(name)?;
+        }
+        Some(current)
+    }
+
+    /// The directory a new entry at path would go in, checking nothing else has its name
+    fn parent_for(&mut self, path: &Path, kind: &str) -> Result<&mut Directory, String> {
+        let parent = path
+            .parent()
+            .ok_or_else(|| format!("Can't make the root a {}", kind))?;
+        let directory = self
+            .directory_mut(&parent)
+            .ok_or_else(|| format!("Can't add {} as {} isn't a directory", path, parent))?;
+        Ok(directory)
+    }
+
+    /// Makes an empty directory at path if there isn't one already, returning whether
+    /// it was new. Its parent has to exist already.
+    pub fn make_directory(&mut self, path: &Path) -> Result<bool, String> {
+        let name = path.name().to_owned();
+        let parent = self.parent_for(path, "directory")?;
+        if parent.file(&name).is_some() {
+            return Err(format!("Can't make directory {} as it's a file", path));
+        }
+        if parent.directory(&name).is_some() {
+            return Ok(false);
+        }
+        parent.directories.push(Directory::new_empty(&name));
+        Ok(true)
+    }
+
+    /// Puts a file at path with the given size, returning the size it had before if it
+    /// was already there. Its directory has to exist already.
+    pub fn add_file(&mut self, path: &Path, size: usize) -> Result<Option<usize>, String> {
+        let name = path.name().to_owned();
+        let parent = self.parent_for(path, "file")?;
+        if parent.directory(&name).is_some() {
+            return Err(format!("Can't add file {} as it's a directory", path));
+        }
+        match parent.files.iter_mut().find(|f| f.name == name) {
+            Some(f) => Ok(Some(std::mem::replace(&mut f.size, size))),
+            None => {
+                parent.files.push(File::new(&name, size));
+                Ok(None)
+            }
+        }
+    }
+
+    /// The total size of every directory from path down, each directory's children
+    /// before itself like `du`
+    pub fn du(&self, path: &Path) -> Option<Vec<(Path, usize)>> {
+        let mut sizes = Vec::new();
+        self.directory(path)?.du(path, &mut sizes);
+        Some(sizes)
+    }
+
+    /// Every file from path down whose size matches the filter, like `find -size`
+    pub fn find_size(&self, path: &Path, filter: SizeFilter) -> Option<Vec<(Path, usize)>> {
+        let mut found = Vec::new();
+        self.directory(path)?.find_files(path, filter, &mut found);
+        Some(found)
+    }
+
+    /// Draws everything from path down like `tree`, with the total size of each
+    /// directory and the size of each file
+    pub fn tree(&self, path: &Path) -> Option<String> {
+        let directory = self.directory(path)?;
+        let mut out = format!("{} (dir, {})\n", path, directory.total_size());
+        directory.draw_tree("", &mut out);
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+fn sample_filesystem() -> Filesystem {
+    let mut fs = Filesystem::new();
+    let root = Path::root();
+    fs.make_directory(&root.join("a")).unwrap();
+    fs.make_directory(&root.resolve("a/e").unwrap()).unwrap();
+    fs.add_file(&root.resolve("/a/e/i").unwrap(), 584).unwrap();
+    fs.add_file(&root.resolve("a/f").unwrap(), 29116).unwrap();
+    fs.add_file(&root.join("b.txt"), 14848514).unwrap();
+    fs
+}
+
+#[test]
+fn test_resolve_paths() {
+    let root = Path::root();
+    let a = root.resolve("a").unwrap();
+    let e = a.resolve("e/./").unwrap();
+    assert_eq!(e.to_string(), "/a/e");
+    assert_eq!(e.resolve("..").unwrap(), a);
+    assert_eq!(e.resolve("../../b").unwrap().to_string(), "/b");
+    assert_eq!(e.resolve("/").unwrap(), root);
+    assert_eq!(e.resolve("/c/d").unwrap().to_string(), "/c/d");
+    assert_eq!(e.parent(), Some(a));
+    assert_eq!(root.name(), "/");
+    assert!(root.resolve("..").is_err());
+}
+
+#[test]
+fn test_building_conflicts() {
+    let mut fs = sample_filesystem();
+    assert_eq!(fs.make_directory(&Path::root().join("a")), Ok(false));
+    assert_eq!(
+        fs.add_file(&Path::root().join("b.txt"), 10),
+        Ok(Some(14848514))
+    );
+    assert!(fs.add_file(&Path::root().join("a"), 10).is_err());
+    assert!(fs.make_directory(&Path::root().join("b.txt")).is_err());
+    assert!(fs
+        .make_directory(&Path::root().resolve("x/y").unwrap())
+        .is_err());
+}
+
+#[test]
+fn test_du() {
+    let fs = sample_filesystem();
+    let sizes = fs
+        .du(&Path::root())
+        .unwrap()
+        .into_iter()
+        .map(|(p, s)| (p.to_string(), s))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sizes,
+        vec![
+            ("/a/e".to_owned(), 584),
+            ("/a".to_owned(), 29700),
+            ("/".to_owned(), 14878214)
+        ]
+    );
+    assert_eq!(fs.du(&Path::root().join("nope")), None);
+}
+
+#[test]
+fn test_find_size() {
+    let fs = sample_filesystem();
+    let find = |filter: &str| {
+        fs.find_size(&Path::root(), filter.parse().unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|(p, _)| p.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(find("+28k"), vec!["/b.txt", "/a/f"]);
+    assert_eq!(find("-1k"), vec!["/a/e/i"]);
+    assert_eq!(find("584"), vec!["/a/e/i"]);
+    assert_eq!(find("+14M"), vec!["/b.txt"]);
+    assert!("+x".parse::<SizeFilter>().is_err());
+}
+
+#[test]
+fn test_tree() {
+    let fs = sample_filesystem();
+    assert_eq!(
+        fs.tree(&Path::root()).unwrap(),
+        "/ (dir, 14878214)
+├── a (dir, 29700)
+│   ├── e (dir, 584)
+│   │   └── i (584)
+│   └── f (29116)
+└── b.txt (14848514)
+"
+    );
+}
@@ -0,0 +1,261 @@
+use super::filesystem::{Filesystem, Path};
+use regex::Regex;
+use std::{error::Error, fmt, str::FromStr};
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum Command<'a> {
+    /// Change to the directory at a path, which can be absolute or relative
+    Cd(&'a str),
+    List,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum DirEntry<'a> {
+    /// Directory with name
+    Directory(&'a str),
+    /// File with name and size
+    File(&'a str, usize),
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum InputLine<'a> {
+    Command(Command<'a>),
+    Result(DirEntry<'a>),
+}
+
+fn parse_input_line(line: &str) -> Result<InputLine<'_>, Box<dyn Error>> {
+    lazy_static! {
+        static ref FILE_REGEX: Regex = Regex::new(r"^(\d+) (.+)$").unwrap();
+    }
+
+    let line = line.trim();
+    if line == "$ ls" {
+        Ok(InputLine::Command(Command::List))
+    } else if let Some(path) = line.strip_prefix("$ cd ") {
+        Ok(InputLine::Command(Command::Cd(path.trim())))
+    } else if let Some(dirname) = line.strip_prefix("dir ") {
+        Ok(InputLine::Result(DirEntry::Directory(dirname)))
+    } else {
+        let mut parts = line.split_whitespace();
+        let size = parts
+            .next()
+            .ok_or_else(|| format!("Input line '{}' could not be parsed", line))?;
+        let name = parts.next().ok_or_else(|| {
+            format!(
+                "Input line '{}' could not be parsed: expecting file entry, but no second part",
+                line
+            )
+        })?;
+        let size = usize::from_str(size)?;
+        Ok(InputLine::Result(DirEntry::File(name, size)))
+    }
+}
+
+pub fn parse_input(input: &str) -> Result<Vec<InputLine<'_>>, Box<dyn Error>> {
+    input.lines().map(parse_input_line).collect()
+}
+
+/// Something in a transcript which doesn't agree with what came before it
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Problem {
+    /// A file was listed again with a different size. The later size is kept.
+    SizeChanged { path: Path, was: usize, now: usize },
+    /// A cd went into a directory which hadn't been listed. It's made so the replay
+    /// can carry on.
+    UnlistedDirectory(Path),
+    /// A cd tried to go above the root, so stayed where it was
+    AboveRoot(String),
+    /// A name was listed as both a file and a directory. The first one is kept.
+    FileAndDirectory(String),
+    /// Directory entries appeared without an ls before them
+    OutputWithoutList,
+}
+
+/// A problem found at a 1-based line of the transcript
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Inconsistency {
+    pub line: usize,
+    pub problem: Problem,
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: ", self.line)?;
+        match &self.problem {
+            Problem::SizeChanged { path, was, now } => write!(
+                f,
+                "{} was listed with {} bytes before but now has {}",
+                path, was, now
+            ),
+            Problem::UnlistedDirectory(path) => {
+                write!(f, "cd into {}, which was never listed", path)
+            }
+            Problem::AboveRoot(message) | Problem::FileAndDirectory(message) => {
+                write!(f, "{}", message)
+            }
+            Problem::OutputWithoutList => write!(f, "directory entries without an ls"),
+        }
+    }
+}
+
+/// The filesystem a transcript describes, along with anything in it which didn't add up
+pub struct Replay {
+    pub filesystem: Filesystem,
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+/// Rebuilds the filesystem from a transcript of cd and ls commands and their output,
+/// carrying on past anything inconsistent so all of it can be reported
+pub fn replay(transcript: &[InputLine]) -> Replay {
+    let mut filesystem = Filesystem::new();
+    let mut inconsistencies = Vec::new();
+    let mut current = Path::root();
+    let mut listing = false;
+
+    for (i, input_line) in transcript.iter().enumerate() {
+        let mut flag = |problem| {
+            inconsistencies.push(Inconsistency {
+                line: i + 1,
+                problem,
+            })
+        };
+        match input_line {
+            InputLine::Command(Command::Cd(path)) => {
+                listing = false;
+                match current.resolve(path) {
+                    Ok(target) => {
+                        // go down one directory at a time to find the first unlisted one
+                        let mut partial = Path::root();
+                        for name in target.components() {
+                            partial = partial.join(name);
+                            match filesystem.make_directory(&partial) {
+                                Ok(false) => {}
+                                Ok(true) => flag(Problem::UnlistedDirectory(partial.clone())),
+                                Err(message) => {
+                                    flag(Problem::FileAndDirectory(message));
+                                    break;
+                                }
+                            }
+                        }
+                        if filesystem.directory(&target).is_some() {
+                            current = target;
+                        }
+                    }
+                    Err(message) => flag(Problem::AboveRoot(message)),
+                }
+            }
+            InputLine::Command(Command::List) => listing = true,
+            InputLine::Result(entry) => {
+                if !listing {
+                    flag(Problem::OutputWithoutList);
+                    // only say so once for each run of entries
+                    listing = true;
+                }
+                match entry {
+                    DirEntry::Directory(name) => {
+                        if let Err(message) = filesystem.make_directory(&current.join(name)) {
+                            flag(Problem::FileAndDirectory(message));
+                        }
+                    }
+                    DirEntry::File(name, size) => {
+                        let path = current.join(name);
+                        match filesystem.add_file(&path, *size) {
+                            Ok(Some(was)) if was != *size => flag(Problem::SizeChanged {
+                                path,
+                                was,
+                                now: *size,
+                            }),
+                            Ok(_) => {}
+                            Err(message) => flag(Problem::FileAndDirectory(message)),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Replay {
+        filesystem,
+        inconsistencies,
+    }
+}
+
+#[test]
+fn test_parse_input() {
+    let input = "$ cd /
+$ ls
+dir a
+34 a.txt
+$ cd a
+$ cd ..";
+    let parsed = parse_input(input).expect("Should parse");
+    assert_eq!(
+        parsed,
+        vec![
+            InputLine::Command(Command::Cd("/")),
+            InputLine::Command(Command::List),
+            InputLine::Result(DirEntry::Directory("a")),
+            InputLine::Result(DirEntry::File("a.txt", 34)),
+            InputLine::Command(Command::Cd("a")),
+            InputLine::Command(Command::Cd(".."))
+        ]
+    );
+}
+
+#[test]
+fn test_replay_paths() {
+    let transcript = parse_input(
+        "$ ls
+dir a
+$ cd a
+$ ls
+dir b
+$ cd b/..//b
+$ ls
+5 x
+$ cd /a/b
+$ ls
+5 x",
+    )
+    .unwrap();
+    let replay = replay(&transcript);
+    assert_eq!(replay.inconsistencies, vec![]);
+    let x = Path::root().resolve("a/b").unwrap();
+    assert_eq!(replay.filesystem.directory(&x).unwrap().total_size(), 5);
+}
+
+#[test]
+fn test_replay_flags_inconsistencies() {
+    let transcript = parse_input(
+        "$ cd /
+$ ls
+dir a
+10 f
+$ cd ..
+$ cd b
+7 g
+$ cd /
+$ ls
+dir f
+12 f",
+    )
+    .unwrap();
+    let replay = replay(&transcript);
+    let messages = replay
+        .inconsistencies
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        vec![
+            "Line 5: '..' goes above the root from /",
+            "Line 6: cd into /b, which was never listed",
+            "Line 7: directory entries without an ls",
+            "Line 10: Can't make directory /f as it's a file",
+            "Line 11: /f was listed with 10 bytes before but now has 12",
+        ]
+    );
+    // the replay carries on, so g still ends up in b
+    assert_eq!(replay.filesystem.root().total_size(), 19);
+}
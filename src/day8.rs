@@ -2,6 +2,8 @@ use std::error::Error;
 
 use crate::common::day;
 use crate::common::grid::Grid;
+use crate::common::image::{self, Colour};
+use crate::common::raycast::CARDINALS;
 
 pub struct Day8 {
     input: &'static str,
@@ -18,6 +20,9 @@ impl Day8 {
 impl day::Day for Day8 {
     fn run(&mut self) -> day::Result {
         let plantation = parse_input(self.input)?;
+        if let Some(path) = image::output_path("day8-trees.png") {
+            image::save(&plantation, path, 4, |h| Colour::new(0, 40 + h * 20, 0))?;
+        }
         let part1_visible_trees = count_visible_trees(&plantation);
         let part2_most_scenic_score = find_most_scenic_tree(&plantation)?;
         Ok((
@@ -34,7 +39,7 @@ fn parse_input(input: &str) -> Result<Grid<u8>, Box<dyn Error>> {
     let chars: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
     let grid_height = chars.len();
     let grid_width = chars
-        .get(0)
+        .first()
         .ok_or_else(|| "No content in input".to_owned())?
         .len();
     let mut grid = Grid::new(grid_width, grid_height);
@@ -70,68 +75,29 @@ fn u8_from_digit(c: &char) -> Result<u8, String> {
 
 fn count_visible_trees(plantation: &Grid<u8>) -> usize {
     plantation
-        .iter_coords()
-        .filter(|(x, y)| tree_is_visible(plantation, *x, *y))
-        .count()
-}
-
-fn tree_is_visible(plantation: &Grid<u8>, x: usize, y: usize) -> bool {
-    let this_tree_height = plantation
-        .get(x, y)
-        .expect("If we got bad coords from iter_coords there is a bug in the Grid");
-    let all_above = plantation
-        .all_above(x, y, |t| t < this_tree_height)
-        .expect("bug in grid");
-    let all_below = plantation
-        .all_below(x, y, |t| t < this_tree_height)
-        .expect("bug in grid");
-    let all_right_of = plantation
-        .all_right_of(x, y, |t| t < this_tree_height)
-        .expect("bug in grid");
-    let all_left_of = plantation
-        .all_left_of(x, y, |t| t < this_tree_height)
-        .expect("bug in grid");
-
-    all_above || all_below || all_right_of || all_left_of
-}
-
-fn take_shorter_trees_then_first_taller<'a, I: Iterator<Item = &'a u8>>(
-    get_iter: impl Fn() -> I,
-    this_tree_height: u8,
-) -> impl Iterator<Item = &'a u8> {
-    get_iter()
-        .take_while(move |t| **t < this_tree_height)
-        .chain(
-            get_iter()
-                .skip_while(move |t| **t < this_tree_height)
-                .take(1),
-        )
+        .visible_from_edges(|height| *height)
+        .count(|visible| *visible)
 }
 
+/// Score of a single tree, casting a ray each way rather than doing the whole grid at once
+#[cfg(test)]
 fn scenic_score_of(plantation: &Grid<u8>, x: usize, y: usize) -> Result<u32, Box<dyn Error>> {
-    let this_tree_height = plantation.get(x, y)?;
-    let visible_left =
-        take_shorter_trees_then_first_taller(|| plantation.iter_left_of(x, y), *this_tree_height)
-            .count() as u32;
-    let visible_right =
-        take_shorter_trees_then_first_taller(|| plantation.iter_right_of(x, y), *this_tree_height)
-            .count() as u32;
-    let visible_above =
-        take_shorter_trees_then_first_taller(|| plantation.iter_above(x, y), *this_tree_height)
-            .count() as u32;
-    let visible_below =
-        take_shorter_trees_then_first_taller(|| plantation.iter_below(x, y), *this_tree_height)
-            .count() as u32;
-
-    Ok(visible_above * visible_below * visible_left * visible_right)
+    let this_tree_height = *plantation.get(x, y)?;
+    CARDINALS.iter().try_fold(1, |score, direction| {
+        let hit = plantation.cast(x, y, *direction, |t| *t >= this_tree_height)?;
+        Ok(score * hit.distance as u32)
+    })
 }
 
 fn find_most_scenic_tree(plantation: &Grid<u8>) -> Result<u32, Box<dyn Error>> {
-    Ok(plantation
-        .iter_coords()
-        .map(|(x, y)| scenic_score_of(plantation, x, y))
-        .collect::<Result<Vec<u32>, _>>()?
-        .into_iter()
+    let mut scores = plantation.map(|_| 1);
+    for direction in CARDINALS {
+        let sightlines = plantation.sightlines(direction, |height| *height)?;
+        scores = scores.zip_with(&sightlines, |score, s| score * s.distance as u32)?;
+    }
+    Ok(scores
+        .enumerate()
+        .map(|(_, score)| *score)
         .max()
         .ok_or_else(|| "No trees".to_owned())?)
 }
@@ -1,4 +1,9 @@
-use std::{cmp::Ordering, collections::HashSet, error::Error, str::FromStr};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    error::Error,
+    str::FromStr,
+};
 
 use itertools::Itertools;
 
@@ -65,7 +70,7 @@ impl Move {
         }
     }
     fn expand(&self) -> impl Iterator<Item = Step> {
-        std::iter::repeat(self.to_step()).take(self.count())
+        std::iter::repeat_n(self.to_step(), self.count())
     }
 }
 
@@ -102,14 +107,32 @@ fn run_part2(steps: impl Iterator<Item = Step>) -> usize {
     do_steps_to_rope(Rope::new_by_count(10), steps)
 }
 
-fn do_steps_to_rope(mut rope: Rope, steps: impl Iterator<Item = Step>) -> usize {
-    let mut tail_visited = HashSet::new();
-    tail_visited.insert(rope.tail());
-    for step in steps {
-        rope.apply_step(step);
-        tail_visited.insert(rope.tail());
+fn do_steps_to_rope(rope: Rope, steps: impl Iterator<Item = Step>) -> usize {
+    let tail = rope.knots.len() - 1;
+    let mut simulation = Simulation::new(rope);
+    simulation.run(steps);
+    simulation.visited(tail)
+}
+
+/// Prints every frame of a rope with the given number of knots following the steps in
+/// a file, or the puzzle input if there's no file, then how many places the tail
+/// visited. The follow rule can be diagonal, orthogonal or snake.
+pub fn print_frames(path: Option<&str>, knots: usize, follow: &str) -> Result<(), Box<dyn Error>> {
+    if knots < 2 {
+        return Err("A rope needs at least two knots".into());
+    }
+    let steps = match path {
+        Some(path) => parse_input(&std::fs::read_to_string(path)?)?,
+        None => parse_input(Day9::new().input)?,
+    };
+    let mut simulation = Simulation::new(Rope::new_by_count(knots).following(follow.parse()?));
+    simulation.run(steps.into_iter());
+    let viewport = simulation.viewport();
+    for step in 0..=simulation.steps() {
+        println!("{}", simulation.frame(step, viewport));
     }
-    tail_visited.len()
+    println!("The tail visited {} places", simulation.visited(knots - 1));
+    Ok(())
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
@@ -154,37 +177,101 @@ impl Location {
     }
 }
 
+/// How each knot moves to catch up with the one in front when they stop touching
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+enum Follow {
+    /// One step towards the knot in front, diagonally if they're not in line
+    #[default]
+    Diagonal,
+    /// One step towards the knot in front along whichever axis it's further away on,
+    /// never diagonally
+    Orthogonal,
+    /// Straight to where the knot in front was before it moved, like a snake's body
+    Snake,
+}
+
+impl FromStr for Follow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "diagonal" => Ok(Follow::Diagonal),
+            "orthogonal" => Ok(Follow::Orthogonal),
+            "snake" => Ok(Follow::Snake),
+            _ => Err(format!(
+                "Unknown follow rule '{}', expected diagonal, orthogonal or snake",
+                s
+            )),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 struct Rope {
     knots: Vec<Location>,
+    follow: Follow,
 }
 
 impl Rope {
     fn new(hx: isize, hy: isize, tx: isize, ty: isize) -> Self {
         Self {
             knots: vec![Location::new(hx, hy), Location::new(tx, ty)],
+            follow: Follow::default(),
         }
     }
 
     fn new_by_count(count: usize) -> Self {
         Self {
-            knots: std::iter::repeat(Location::new(0, 0)).take(count).collect(),
+            knots: std::iter::repeat_n(Location::new(0, 0), count).collect(),
+            follow: Follow::default(),
         }
     }
 
+    fn following(mut self, follow: Follow) -> Self {
+        self.follow = follow;
+        self
+    }
+
+    #[cfg(test)]
     fn tail(&self) -> Location {
         self.knots[self.knots.len() - 1]
     }
 
     fn apply_step(&mut self, step: Step) {
+        let head_was = self.knots[0];
         self.knots[0] = self.knots[0].apply_step(step);
-        self.correct_tail();
+        self.correct_tail_from(head_was);
     }
 
+    #[cfg(test)]
     fn correct_tail(&mut self) {
+        self.correct_tail_from(self.knots[0]);
+    }
+
+    /// Moves each knot after the head to catch up with the one in front, given where the
+    /// head was before it moved
+    fn correct_tail_from(&mut self, head_was: Location) {
+        // where the knot in front was before this step, which only the snake rule needs
+        let mut leader_was = head_was;
         let indexes = (0..self.knots.len()).tuple_windows().collect::<Vec<_>>();
         for (i, j) in indexes {
-            Rope::correct_pair(*self.knots.get(i).unwrap(), self.knots.get_mut(j).unwrap())
+            let head = self.knots[i];
+            let tail = &mut self.knots[j];
+            let tail_was = *tail;
+            if !head.touches(tail) {
+                match self.follow {
+                    Follow::Diagonal => Rope::correct_pair(head, tail),
+                    Follow::Orthogonal => {
+                        if (head.x - tail.x).abs() >= (head.y - tail.y).abs() {
+                            tail.x += (head.x - tail.x).signum();
+                        } else {
+                            tail.y += (head.y - tail.y).signum();
+                        }
+                    }
+                    Follow::Snake => *tail = leader_was,
+                }
+            }
+            leader_was = tail_was;
         }
     }
 
@@ -218,6 +305,124 @@ impl Rope {
     }
 }
 
+/// A rope moving through a series of steps, remembering where every knot was after
+/// each one so any point can be drawn afterwards
+struct Simulation {
+    rope: Rope,
+    start: Location,
+    /// Where each knot has been, starting from before the first step
+    history: Vec<Vec<Location>>,
+}
+
+impl Simulation {
+    fn new(rope: Rope) -> Self {
+        let history = rope.knots.iter().map(|k| vec![*k]).collect();
+        Self {
+            start: rope.knots[0],
+            rope,
+            history,
+        }
+    }
+
+    fn step(&mut self, step: Step) {
+        self.rope.apply_step(step);
+        for (history, knot) in self.history.iter_mut().zip(&self.rope.knots) {
+            history.push(*knot);
+        }
+    }
+
+    fn run(&mut self, steps: impl Iterator<Item = Step>) {
+        for step in steps {
+            self.step(step);
+        }
+    }
+
+    /// How many steps have been taken
+    fn steps(&self) -> usize {
+        self.history[0].len() - 1
+    }
+
+    /// Where a knot has been, counting from the head, with one location for before the
+    /// first step and one after each step
+    fn history(&self, knot: usize) -> &[Location] {
+        &self.history[knot]
+    }
+
+    /// How many different places a knot has been
+    fn visited(&self, knot: usize) -> usize {
+        self.history(knot).iter().collect::<HashSet<_>>().len()
+    }
+
+    /// The smallest viewport showing every knot at every step, so frames line up
+    fn viewport(&self) -> Viewport {
+        Viewport::fit(self.history.iter().flatten().copied())
+            .expect("every rope has at least one knot")
+    }
+
+    /// Draws the rope as it was after the given number of steps, like the puzzle does:
+    /// `H` for the head, `T` for the tail of a two knot rope, otherwise the number of
+    /// each knot, and `s` for the start if nothing covers it. Knots nearer the head
+    /// cover later ones.
+    fn frame(&self, step: usize, viewport: Viewport) -> String {
+        let mut canvas = Canvas::default();
+        let knots = self.history.len();
+        for (i, history) in self.history.iter().enumerate() {
+            let label = match i {
+                0 => 'H',
+                1 if knots == 2 => 'T',
+                _ => std::char::from_digit(i as u32, 10).unwrap_or('*'),
+            };
+            canvas.draw(history[step], label);
+        }
+        canvas.draw(self.start, 's');
+        canvas.render(viewport)
+    }
+}
+
+/// The rectangle of locations a frame shows, including both corners
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Viewport {
+    min: Location,
+    max: Location,
+}
+
+impl Viewport {
+    /// The smallest viewport containing all the locations, or None if there are none
+    fn fit(locations: impl IntoIterator<Item = Location>) -> Option<Self> {
+        locations.into_iter().fold(None, |viewport, l| {
+            Some(match viewport {
+                None => Viewport { min: l, max: l },
+                Some(Viewport { min, max }) => Viewport {
+                    min: Location::new(min.x.min(l.x), min.y.min(l.y)),
+                    max: Location::new(max.x.max(l.x), max.y.max(l.y)),
+                },
+            })
+        })
+    }
+}
+
+/// Characters drawn at only the locations which have something there
+#[derive(Debug, Default)]
+struct Canvas(HashMap<Location, char>);
+
+impl Canvas {
+    /// Draws at a location unless something has already been drawn there
+    fn draw(&mut self, location: Location, c: char) {
+        self.0.entry(location).or_insert(c);
+    }
+
+    fn render(&self, viewport: Viewport) -> String {
+        let mut out = String::new();
+        for y in viewport.min.y..=viewport.max.y {
+            for x in viewport.min.x..=viewport.max.x {
+                out.push(*self.0.get(&Location::new(x, y)).unwrap_or(&'.'));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
 #[test]
 fn test_correct_tail() {
     let mut rope = Rope::new(2, 1, 1, 1);
@@ -298,3 +503,80 @@ R 2";
     let visited = run_part1(parse_input(input).unwrap().into_iter());
     assert_eq!(visited, 13);
 }
+
+#[cfg(test)]
+const SAMPLE: &str = "R 4
+U 4
+L 3
+D 1
+R 4
+D 1
+L 5
+R 2";
+
+#[test]
+fn test_history_and_frames() {
+    let mut simulation = Simulation::new(Rope::new_by_count(10));
+    simulation.run(parse_input("R 4\nU 4").unwrap().into_iter());
+    assert_eq!(simulation.steps(), 8);
+    assert_eq!(simulation.history(0)[4], Location::new(4, 0));
+    assert_eq!(simulation.history(1)[8], Location::new(4, -3));
+    assert_eq!(simulation.visited(9), 1);
+
+    let viewport = simulation.viewport();
+    assert_eq!(
+        viewport,
+        Viewport {
+            min: Location::new(0, -4),
+            max: Location::new(4, 0)
+        }
+    );
+    // the same frame as the puzzle shows after U 4
+    assert_eq!(
+        simulation.frame(8, viewport),
+        "....H
+....1
+..432
+.5...
+6....
+"
+    );
+    assert_eq!(
+        simulation.frame(0, viewport),
+        ".....
+.....
+.....
+.....
+H....
+"
+    );
+}
+
+#[test]
+fn test_two_knot_frame() {
+    let mut simulation = Simulation::new(Rope::new_by_count(2));
+    simulation.run(parse_input("R 2").unwrap().into_iter());
+    assert_eq!(simulation.frame(2, simulation.viewport()), "sTH\n");
+}
+
+#[test]
+fn test_follow_rules() {
+    let visited = |follow: Follow, knots: usize| {
+        let mut simulation = Simulation::new(Rope::new_by_count(knots).following(follow));
+        simulation.run(parse_input(SAMPLE).unwrap().into_iter());
+        simulation.visited(knots - 1)
+    };
+    assert_eq!(visited(Follow::Diagonal, 2), 13);
+    // with two knots the tail always ends up where the head just was either way
+    assert_eq!(visited(Follow::Snake, 2), 13);
+    assert_eq!(visited(Follow::Diagonal, 10), 1);
+
+    let mut rope = Rope::new(2, 2, 1, 0).following(Follow::Orthogonal);
+    rope.correct_tail();
+    assert_eq!(
+        rope.tail(),
+        Location::new(1, 1),
+        "moves along y, never diagonally"
+    );
+    assert!("sideways".parse::<Follow>().is_err());
+}
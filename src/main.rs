@@ -1,5 +1,9 @@
-use clap::{App, Arg};
-use std::time::Instant;
+use clap::{App, AppSettings, Arg, SubCommand};
+use std::{
+    error::Error,
+    io,
+    time::{Duration, Instant},
+};
 
 #[macro_use]
 extern crate lazy_static;
@@ -39,8 +43,155 @@ fn main() {
                 .required(true)
                 .index(1),
         )
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .subcommand(
+            SubCommand::with_name("debug")
+                .about("Steps through a .cpu program interactively")
+                .arg(
+                    Arg::with_name("PROGRAM")
+                        .help("The program to debug")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("ISA")
+                        .long("isa")
+                        .takes_value(true)
+                        .help("A spec for the instruction set, instead of the handheld's"),
+                )
+                .arg(
+                    Arg::with_name("TRACE")
+                        .long("trace")
+                        .help("Prints every cycle of a run instead of stepping through it"),
+                )
+                .arg(
+                    Arg::with_name("AT")
+                        .long("at")
+                        .takes_value(true)
+                        .conflicts_with("TRACE")
+                        .help(
+                            "Runs the program, shows what happened during this cycle and \
+                             what last set X, then steps through from there",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("MAX_CYCLES")
+                        .long("max-cycles")
+                        .takes_value(true)
+                        .default_value("1000000")
+                        .help("How many cycles to run for --trace or --at before giving up"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("crates")
+                .about("Animates the crane moving the crates for day 5")
+                .arg(
+                    Arg::with_name("CRANE")
+                        .long("crane")
+                        .takes_value(true)
+                        .default_value("9001")
+                        .help("The crane to use: 9000, 9001, flipping or limit=N"),
+                )
+                .arg(
+                    Arg::with_name("DELAY")
+                        .long("delay")
+                        .takes_value(true)
+                        .default_value("100")
+                        .help("Milliseconds to wait between each move"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("fs")
+                .about(
+                    "Asks about the filesystem from day 7, like du, find -size or tree, \
+                     or exports it as JSON or an SVG treemap",
+                )
+                .setting(AppSettings::TrailingVarArg)
+                .arg(
+                    Arg::with_name("QUERY")
+                        .help(
+                            "du [PATH], find [PATH] -size N, tree [PATH], json [PATH] \
+                             or svg [PATH]",
+                        )
+                        .required(true)
+                        .multiple(true)
+                        .allow_hyphen_values(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("rope")
+                .about("Draws each step of the rope from day 9")
+                .arg(
+                    Arg::with_name("INPUT")
+                        .help("A file of moves, instead of the puzzle input")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("KNOTS")
+                        .long("knots")
+                        .takes_value(true)
+                        .default_value("10")
+                        .help("How many knots the rope has, including the head"),
+                )
+                .arg(
+                    Arg::with_name("FOLLOW")
+                        .long("follow")
+                        .takes_value(true)
+                        .default_value("diagonal")
+                        .help("How knots catch up: diagonal, orthogonal or snake"),
+                ),
+        )
         .get_matches();
 
+    if let Some(debug) = matches.subcommand_matches("debug") {
+        let program = debug.value_of("PROGRAM").expect("Program must be provided");
+        if let Err(e) = debug_program(
+            program,
+            debug.value_of("ISA"),
+            debug.is_present("TRACE"),
+            debug.value_of("AT"),
+            debug
+                .value_of("MAX_CYCLES")
+                .expect("Max cycles has a default"),
+        ) {
+            log::error!("{}", e);
+        }
+        return;
+    }
+
+    if let Some(crates) = matches.subcommand_matches("crates") {
+        if let Err(e) = animate_crates(
+            crates.value_of("CRANE").expect("Crane has a default"),
+            crates.value_of("DELAY").expect("Delay has a default"),
+        ) {
+            log::error!("{}", e);
+        }
+        return;
+    }
+
+    if let Some(fs) = matches.subcommand_matches("fs") {
+        let query = fs
+            .values_of("QUERY")
+            .expect("Query must be provided")
+            .collect::<Vec<_>>();
+        match day7::query(&query) {
+            Ok(answer) => print!("{}", answer),
+            Err(e) => log::error!("{}", e),
+        }
+        return;
+    }
+
+    if let Some(rope) = matches.subcommand_matches("rope") {
+        if let Err(e) = draw_rope(
+            rope.value_of("INPUT"),
+            rope.value_of("KNOTS").expect("Knots has a default"),
+            rope.value_of("FOLLOW").expect("Follow has a default"),
+        ) {
+            log::error!("{}", e);
+        }
+        return;
+    }
+
     let day = matches.value_of("DAY").expect("Day must be provided");
 
     match day {
@@ -77,6 +228,52 @@ fn run_day(day_num: u8, day_func: impl Fn() -> common::day::Result) {
     log::info!("Time taken: {} seconds", elapsed.as_secs_f32());
 }
 
+fn debug_program(
+    path: &str,
+    isa_path: Option<&str>,
+    trace: bool,
+    at: Option<&str>,
+    max_cycles: &str,
+) -> Result<(), Box<dyn Error>> {
+    let isa = match isa_path {
+        Some(isa_path) => std::fs::read_to_string(isa_path)?.parse()?,
+        None => common::isa::Isa::handheld(),
+    };
+    let mut cpu = common::cpu::Cpu::compile_for(isa, &std::fs::read_to_string(path)?)?;
+    if !trace && at.is_none() {
+        return common::debugger::Debugger::new(cpu)
+            .run_interactive(io::stdin().lock(), io::stdout());
+    }
+
+    let recording = common::trace::Trace::record(&mut cpu, max_cycles.parse()?)?;
+    let cycle = match at {
+        Some(cycle) => cycle.parse()?,
+        None => {
+            print!("{}", recording);
+            return Ok(());
+        }
+    };
+    let entry = recording
+        .entry(cycle)
+        .ok_or_else(|| format!("The program never ran cycle {}", cycle))?;
+    // the labels are the same width so the two cycles line up
+    println!("During {}", entry);
+    match recording.last_change_before(cycle) {
+        Some(change) => println!("Set X  {}", change),
+        None => println!("X still had its starting value"),
+    }
+    recording.rewind(&mut cpu, cycle);
+    common::debugger::Debugger::new(cpu).run_interactive(io::stdin().lock(), io::stdout())
+}
+
+fn animate_crates(crane: &str, delay: &str) -> Result<(), Box<dyn Error>> {
+    day5::animate(crane, Duration::from_millis(delay.parse()?))
+}
+
+fn draw_rope(path: Option<&str>, knots: &str, follow: &str) -> Result<(), Box<dyn Error>> {
+    day9::print_frames(path, knots.parse()?, follow)
+}
+
 fn render_result((part1, part2): (Option<String>, Option<String>)) -> String {
     format!(
         "=== PART 1 ===\n\n{}\n\n=== PART 2 ===\n\n{}\n\n",